@@ -0,0 +1,153 @@
+use crate::modifiers::Modifiers;
+use crate::sampling::Sampling;
+use crate::TargetStringType;
+use std::fmt;
+use std::str::FromStr;
+
+/// Bumped whenever the same code would start a different test
+const VERSION: &str = "st1";
+
+/// Everything needed to start exactly the same test again, written as a short code
+/// like `st1-w50-en1k-8f3a`: the mode, the dictionary and the seed in hex. Sampling other
+/// than uniform follows the dictionary, as in `en1k.zipf`, and punctuation and numbers
+/// turned on or off follow the mode, as in `w50.p.n` or `q.p0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub mode: TargetStringType,
+    pub dict: String,
+    pub sampling: Sampling,
+    pub modifiers: Modifiers,
+    pub seed: u64,
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-", VERSION)?;
+        match self.mode {
+            TargetStringType::Timed(n) => write!(f, "t{}", n)?,
+            TargetStringType::Words(n) => write!(f, "w{}", n)?,
+            TargetStringType::Quote => write!(f, "q")?,
+            // Code tests type a local file, so they are never shared, see `App::challenge`
+            TargetStringType::Code => write!(f, "c")?,
+        }
+        for (name, toggle) in [
+            ("p", self.modifiers.punctuation),
+            ("n", self.modifiers.numbers),
+        ] {
+            match toggle {
+                Some(true) => write!(f, ".{}", name)?,
+                Some(false) => write!(f, ".{}0", name)?,
+                None => {}
+            }
+        }
+        write!(f, "-{}", self.dict)?;
+        if self.sampling != Sampling::Uniform {
+            write!(f, ".{}", self.sampling)?;
+        }
+        write!(f, "-{:x}", self.seed)
+    }
+}
+
+impl FromStr for Challenge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |why: &str| format!("invalid challenge code '{}': {}", s, why);
+        let parts: Vec<_> = s.split('-').collect();
+        let (mode, dict, seed) = match parts[..] {
+            [VERSION, mode, dict, seed] => (mode, dict, seed),
+            [version, ..] if version.starts_with("st") && parts.len() == 4 => {
+                return Err(invalid("it is from a different version of shelltyper"))
+            }
+            _ => return Err(invalid("expected something like st1-w50-en1k-8f3a")),
+        };
+        let mut toggles = mode.split('.');
+        let mode = toggles.next().unwrap();
+        let mut modifiers = Modifiers::default();
+        for toggle in toggles {
+            let (name, on) = match toggle.strip_suffix('0') {
+                Some(name) => (name, false),
+                None => (toggle, true),
+            };
+            match name {
+                "p" => modifiers.punctuation = Some(on),
+                "n" => modifiers.numbers = Some(on),
+                _ => {
+                    return Err(invalid(
+                        "punctuation and numbers should be .p, .n, .p0 or .n0",
+                    ))
+                }
+            }
+        }
+        let count = || match mode[1..].parse() {
+            Ok(0) => Err(invalid("a test needs at least one second or word")),
+            Ok(n) => Ok(n),
+            Err(_) => Err(invalid("the mode should be t<seconds>, w<words> or q")),
+        };
+        let mode = match mode.chars().next() {
+            Some('t') => TargetStringType::Timed(count()?),
+            Some('w') => TargetStringType::Words(count()?),
+            Some('q') if mode.len() == 1 => TargetStringType::Quote,
+            _ => return Err(invalid("the mode should be t<seconds>, w<words> or q")),
+        };
+        let (dict, sampling) = match dict.split_once('.') {
+            Some((dict, sampling)) => (dict, sampling.parse().map_err(|e: String| invalid(&e))?),
+            None => (dict, Sampling::Uniform),
+        };
+        let seed = u64::from_str_radix(seed, 16).map_err(|_| invalid("the seed is not hex"))?;
+        Ok(Challenge {
+            mode,
+            dict: dict.to_owned(),
+            sampling,
+            modifiers,
+            seed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_codes() {
+        let challenge: Challenge = "st1-w50-en1k-8f3a".parse().unwrap();
+        assert_eq!(challenge.mode, TargetStringType::Words(50));
+        assert_eq!(challenge.dict, "en1k");
+        assert_eq!(challenge.seed, 0x8f3a);
+        assert_eq!(challenge.to_string(), "st1-w50-en1k-8f3a");
+        let quote = Challenge {
+            mode: TargetStringType::Quote,
+            dict: "en".into(),
+            sampling: Sampling::Uniform,
+            modifiers: Modifiers {
+                punctuation: Some(false),
+                numbers: None,
+            },
+            seed: 255,
+        };
+        assert_eq!(quote.to_string().parse(), Ok(quote));
+        let top: Challenge = "st1-t30-en1k.top100-ff".parse().unwrap();
+        assert_eq!(
+            (top.dict.as_str(), top.sampling),
+            ("en1k", Sampling::Top(100))
+        );
+        assert_eq!(top.to_string(), "st1-t30-en1k.top100-ff");
+        let punctuated: Challenge = "st1-w50.p.n-en-1".parse().unwrap();
+        assert_eq!(punctuated.modifiers.of(punctuated.mode), (true, true));
+        assert_eq!(punctuated.to_string(), "st1-w50.p.n-en-1");
+        for bad in [
+            "st1-w-en-1",
+            "st1-w0-en-1",
+            "st1-t0-en-1",
+            "st1-x5-en-1",
+            "st1-t15-en-xyz",
+            "st2-t15-en-1",
+            "st1-t15-en.top0-1",
+            "st1-t15.x-en-1",
+            "w50",
+        ] {
+            assert!(bad.parse::<Challenge>().is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Where the app reads the current time from
+pub trait Clock: Debug {
+    fn now(&self) -> Instant;
+}
+
+/// The real time
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Time that can be paused, sped up and moved around, for replaying a recorded test.
+/// It starts paused at zero
+#[derive(Debug)]
+pub struct VirtualClock {
+    /// The clock virtual time moves along with while it runs
+    real: Rc<dyn Clock>,
+    /// The instant of virtual time zero
+    origin: Instant,
+    /// Virtual time elapsed when the real clock read `anchor`
+    elapsed: Cell<Duration>,
+    anchor: Cell<Instant>,
+    speed: Cell<u32>,
+    paused: Cell<bool>,
+}
+
+impl VirtualClock {
+    pub fn new() -> VirtualClock {
+        VirtualClock::following(Rc::new(SystemClock))
+    }
+
+    /// Virtual time that runs along with `real`
+    pub fn following(real: Rc<dyn Clock>) -> VirtualClock {
+        let now = real.now();
+        VirtualClock {
+            real,
+            origin: now,
+            elapsed: Cell::new(Duration::from_secs(0)),
+            anchor: Cell::new(now),
+            speed: Cell::new(1),
+            paused: Cell::new(true),
+        }
+    }
+
+    /// Virtual time since zero
+    pub fn elapsed(&self) -> Duration {
+        if self.paused.get() {
+            self.elapsed.get()
+        } else {
+            self.elapsed.get() + (self.real.now() - self.anchor.get()) * self.speed.get()
+        }
+    }
+
+    /// Jump to a point in virtual time, carrying on from there if running
+    pub fn seek(&self, to: Duration) {
+        self.elapsed.set(to);
+        self.anchor.set(self.real.now());
+    }
+
+    pub fn speed(&self) -> u32 {
+        self.speed.get()
+    }
+
+    pub fn set_speed(&self, speed: u32) {
+        self.seek(self.elapsed());
+        self.speed.set(speed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.seek(self.elapsed());
+        self.paused.set(paused);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        VirtualClock::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Real time that only moves when told to
+    #[derive(Debug)]
+    struct ManualClock(Cell<Instant>);
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn virtual_time_follows_real_time_at_its_speed() {
+        let real = Rc::new(ManualClock(Cell::new(Instant::now())));
+        let clock = VirtualClock::following(Rc::clone(&real) as _);
+        let wait = |ms| real.0.set(real.0.get() + Duration::from_millis(ms));
+        let ms = |ms| Duration::from_millis(ms);
+
+        // Starts paused at zero
+        wait(100);
+        assert_eq!(clock.elapsed(), ms(0));
+        clock.set_paused(false);
+        wait(100);
+        assert_eq!(clock.elapsed(), ms(100));
+        clock.set_speed(4);
+        wait(100);
+        assert_eq!(clock.elapsed(), ms(500));
+        clock.set_paused(true);
+        wait(100);
+        assert_eq!(clock.elapsed(), ms(500));
+        assert_eq!(clock.now() - clock.origin, ms(500));
+
+        clock.seek(ms(50));
+        assert_eq!(clock.elapsed(), ms(50));
+        clock.set_paused(false);
+        wait(10);
+        assert_eq!(clock.elapsed(), ms(90));
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+
+/// Columns a tab expands to
+const TAB_WIDTH: usize = 4;
+
+/// A source file to type, keeping its lines and indentation
+#[derive(Debug)]
+pub struct Code {
+    pub name: String,
+    /// (indentation, words) of every non-blank line
+    lines: Vec<(usize, Vec<String>)>,
+}
+
+impl Code {
+    /// Load a source file. Tabs are expanded, runs of spaces inside a line are collapsed
+    /// and blank lines are skipped
+    pub fn load(path: &Path) -> Result<Code, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let lines: Vec<_> = text
+            .lines()
+            .map(|line| line.replace('\t', &" ".repeat(TAB_WIDTH)))
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let indent = line.len() - line.trim_start_matches(' ').len();
+                (indent, line.split_whitespace().map(String::from).collect())
+            })
+            .collect();
+        if lines.is_empty() {
+            return Err("there is no code to type".into());
+        }
+        Ok(Code {
+            name: path
+                .file_name()
+                .map_or_else(|| "code".into(), |s| s.to_string_lossy().into_owned()),
+            lines,
+        })
+    }
+
+    /// Every word with the separator that follows it, a newline at the end of each line.
+    /// The first word of a line carries the line's indentation
+    pub fn words(&self) -> impl Iterator<Item = (String, char)> + '_ {
+        self.lines.iter().flat_map(|(indent, words)| {
+            let last = words.len() - 1;
+            words.iter().enumerate().map(move |(i, word)| {
+                let word = if i == 0 {
+                    format!("{:indent$}{}", "", word, indent = indent)
+                } else {
+                    word.clone()
+                };
+                (word, if i == last { '\n' } else { ' ' })
+            })
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use crate::keys::Key;
+use crate::sampling::Sampling;
+use crate::theme;
+use crate::Correction;
+use serde::de::{Deserializer, Error as _};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tui::style::Color;
+
+/// Settings read from the config file, every one of them optional.
+/// Command line options take precedence over them
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Name of a built in theme, which `colors` then changes
+    pub theme: Option<String>,
+    pub test: TestConfig,
+    pub chart: ChartConfig,
+    pub colors: ColorsConfig,
+    pub keys: KeysConfig,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Timed,
+    Words,
+    Quote,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TestConfig {
+    pub mode: Option<Mode>,
+    /// Seconds of a timed test, or words of a words test
+    pub length: Option<usize>,
+    pub dict: Option<String>,
+    pub sampling: Option<Sampling>,
+    /// Add punctuation or numbers to words, or take them out of quotes
+    pub punctuation: Option<bool>,
+    pub numbers: Option<bool>,
+    pub correction: Option<Correction>,
+    /// Whether finished tests are saved
+    pub history: Option<bool>,
+    pub ghost: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChartConfig {
+    pub height: Option<usize>,
+    pub min_wpm: Option<usize>,
+    pub max_wpm: Option<usize>,
+}
+
+/// Overrides of the colors in `Theme`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorsConfig {
+    #[serde(deserialize_with = "color")]
+    pub background: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub text: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub border: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub completed: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub correct: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub wrong: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub wrong_background: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub untyped: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub current: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub ghost: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub highlight_text: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub ready: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub running: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub done: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub menu: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub wpm: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub accuracy: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub progress: Option<Color>,
+}
+
+/// The keys for each command, either one key or a list of them
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    #[serde(deserialize_with = "keys")]
+    pub restart: Option<Vec<Key>>,
+    #[serde(deserialize_with = "keys")]
+    pub end: Option<Vec<Key>>,
+    #[serde(deserialize_with = "keys")]
+    pub delete_word: Option<Vec<Key>>,
+    #[serde(deserialize_with = "keys")]
+    pub quit: Option<Vec<Key>>,
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    theme::parse_color(&name)
+        .map(Some)
+        .map_err(D::Error::custom)
+}
+
+fn keys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Key>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    let names = match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(name) => vec![name],
+        OneOrMany::Many(names) => names,
+    };
+    names
+        .iter()
+        .map(|name| name.parse().map_err(D::Error::custom))
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// `$XDG_CONFIG_HOME/shelltyper/config.toml`, falling back to `~/.config`
+pub fn default_path() -> Option<PathBuf> {
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config.join("shelltyper").join("config.toml"))
+}
+
+/// Read the config file, which doesn't have to exist
+pub fn load(path: &Path) -> Result<Config, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => toml::from_str(&text).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
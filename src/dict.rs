@@ -1,1023 +1,284 @@
-pub static ENGLISH: [&str; 200] = [
-    "the", "be", "of", "and", "a", "to", "in", "he", "have", "it", "that", "for", "they", "I",
-    "with", "as", "not", "on", "she", "at", "by", "this", "we", "you", "do", "but", "from", "or",
-    "which", "one", "would", "all", "will", "there", "say", "who", "make", "when", "can", "more",
-    "if", "no", "man", "out", "other", "so", "what", "time", "up", "go", "about", "than", "into",
-    "could", "state", "only", "new", "year", "some", "take", "come", "these", "know", "see", "use",
-    "get", "like", "then", "first", "any", "work", "now", "may", "such", "give", "over", "think",
-    "most", "even", "find", "day", "also", "after", "way", "many", "must", "look", "before",
-    "great", "back", "through", "long", "where", "much", "should", "well", "people", "down", "own",
-    "just", "because", "good", "each", "those", "feel", "seem", "how", "high", "too", "place",
-    "little", "world", "very", "still", "nation", "hand", "old", "life", "tell", "write", "become",
-    "here", "show", "house", "both", "between", "need", "mean", "call", "develop", "under", "last",
-    "right", "move", "thing", "general", "school", "never", "same", "another", "begin", "while",
-    "number", "part", "turn", "real", "leave", "might", "want", "point", "form", "off", "child",
-    "few", "small", "since", "against", "ask", "late", "home", "interest", "large", "person",
-    "end", "open", "public", "follow", "during", "present", "without", "again", "hold", "govern",
-    "around", "possible", "head", "consider", "word", "program", "problem", "however", "lead",
-    "system", "set", "order", "eye", "plan", "run", "keep", "face", "fact", "group", "play",
-    "stand", "increase", "early", "course", "change", "help", "line",
-];
+use serde_json::value::RawValue;
+use std::fmt::{self, Debug, Display};
+use std::path::Path;
+use std::rc::Rc;
+
+/// A list of words that typing tests are generated from
+pub trait WordSource: Debug {
+    /// Name used to select this source with `--dict`
+    fn name(&self) -> &str;
+    /// Number of words in the list
+    fn len(&self) -> usize;
+    /// The `i`th word, lists are ordered from most to least frequent where possible
+    fn word(&self, i: usize) -> &str;
+    /// The compiled in list this was loaded from, if it wasn't loaded from a file
+    fn builtin(&self) -> Option<&'static Builtin>;
+}
+
+/// One of the word lists compiled into the binary: the first `size` words of a file with
+/// one word per line, from most to least frequent
+#[derive(Debug, Copy, Clone)]
+pub struct Builtin {
+    name: &'static str,
+    size: usize,
+    text: &'static str,
+}
+
+impl Builtin {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
 
-pub static ENGLISH_1K: [&str; 1000] = [
-    "the",
-    "of",
-    "to",
-    "and",
-    "a",
-    "in",
-    "is",
-    "it",
-    "you",
-    "that",
-    "he",
-    "was",
-    "for",
-    "on",
-    "are",
-    "with",
-    "as",
-    "I",
-    "his",
-    "they",
-    "be",
-    "at",
-    "one",
-    "have",
-    "this",
-    "from",
-    "or",
-    "had",
-    "by",
-    "not",
-    "word",
-    "but",
-    "what",
-    "some",
-    "we",
-    "can",
-    "out",
-    "other",
-    "were",
-    "all",
-    "there",
-    "when",
-    "up",
-    "use",
-    "your",
-    "how",
-    "said",
-    "an",
-    "each",
-    "she",
-    "which",
-    "do",
-    "their",
-    "time",
-    "if",
-    "will",
-    "way",
-    "about",
-    "many",
-    "then",
-    "them",
-    "write",
-    "would",
-    "like",
-    "so",
-    "these",
-    "her",
-    "long",
-    "make",
-    "thing",
-    "see",
-    "him",
-    "two",
-    "has",
-    "look",
-    "more",
-    "day",
-    "could",
-    "go",
-    "come",
-    "did",
-    "number",
-    "sound",
-    "no",
-    "most",
-    "people",
-    "my",
-    "over",
-    "know",
-    "water",
-    "than",
-    "call",
-    "first",
-    "who",
-    "may",
-    "down",
-    "side",
-    "been",
-    "now",
-    "find",
-    "any",
-    "new",
-    "work",
-    "part",
-    "take",
-    "get",
-    "place",
-    "made",
-    "live",
-    "where",
-    "after",
-    "back",
-    "little",
-    "only",
-    "round",
-    "man",
-    "year",
-    "came",
-    "show",
-    "every",
-    "good",
-    "me",
-    "give",
-    "our",
-    "under",
-    "name",
-    "very",
-    "through",
-    "just",
-    "form",
-    "sentence",
-    "great",
-    "think",
-    "say",
-    "help",
-    "low",
-    "line",
-    "differ",
-    "turn",
-    "cause",
-    "much",
-    "mean",
-    "before",
-    "move",
-    "right",
-    "boy",
-    "old",
-    "too",
-    "same",
-    "tell",
-    "does",
-    "set",
-    "three",
-    "want",
-    "air",
-    "well",
-    "also",
-    "play",
-    "small",
-    "end",
-    "put",
-    "home",
-    "read",
-    "hand",
-    "port",
-    "large",
-    "spell",
-    "add",
-    "even",
-    "land",
-    "here",
-    "must",
-    "big",
-    "high",
-    "such",
-    "follow",
-    "act",
-    "why",
-    "ask",
-    "men",
-    "change",
-    "went",
-    "light",
-    "kind",
-    "off",
-    "need",
-    "house",
-    "picture",
-    "try",
-    "us",
-    "again",
-    "animal",
-    "point",
-    "mother",
-    "world",
-    "near",
-    "build",
-    "self",
-    "earth",
-    "father",
-    "head",
-    "stand",
-    "own",
-    "page",
-    "should",
-    "country",
-    "found",
-    "answer",
-    "school",
-    "grow",
-    "study",
-    "still",
-    "learn",
-    "plant",
-    "cover",
-    "food",
-    "sun",
-    "four",
-    "between",
-    "state",
-    "keep",
-    "eye",
-    "never",
-    "last",
-    "let",
-    "thought",
-    "city",
-    "tree",
-    "cross",
-    "farm",
-    "hard",
-    "start",
-    "might",
-    "story",
-    "saw",
-    "far",
-    "sea",
-    "draw",
-    "left",
-    "late",
-    "run",
-    "don't",
-    "while",
-    "press",
-    "close",
-    "night",
-    "real",
-    "life",
-    "few",
-    "north",
-    "open",
-    "seem",
-    "together",
-    "next",
-    "white",
-    "children",
-    "begin",
-    "got",
-    "walk",
-    "example",
-    "ease",
-    "paper",
-    "group",
-    "always",
-    "music",
-    "those",
-    "both",
-    "mark",
-    "often",
-    "letter",
-    "until",
-    "mile",
-    "river",
-    "car",
-    "feet",
-    "care",
-    "second",
-    "book",
-    "carry",
-    "took",
-    "science",
-    "eat",
-    "room",
-    "friend",
-    "began",
-    "idea",
-    "fish",
-    "mountain",
-    "stop",
-    "once",
-    "base",
-    "hear",
-    "horse",
-    "cut",
-    "sure",
-    "watch",
-    "color",
-    "face",
-    "wood",
-    "main",
-    "enough",
-    "plain",
-    "girl",
-    "usual",
-    "young",
-    "ready",
-    "above",
-    "ever",
-    "red",
-    "list",
-    "though",
-    "feel",
-    "talk",
-    "bird",
-    "soon",
-    "body",
-    "dog",
-    "family",
-    "direct",
-    "pose",
-    "leave",
-    "song",
-    "measure",
-    "door",
-    "product",
-    "black",
-    "short",
-    "numeral",
-    "class",
-    "wind",
-    "question",
-    "happen",
-    "complete",
-    "ship",
-    "area",
-    "half",
-    "rock",
-    "order",
-    "fire",
-    "south",
-    "problem",
-    "piece",
-    "told",
-    "knew",
-    "pass",
-    "since",
-    "top",
-    "whole",
-    "king",
-    "space",
-    "heard",
-    "best",
-    "hour",
-    "better",
-    "true",
-    "during",
-    "hundred",
-    "five",
-    "remember",
-    "step",
-    "early",
-    "hold",
-    "west",
-    "ground",
-    "interest",
-    "reach",
-    "fast",
-    "verb",
-    "sing",
-    "listen",
-    "six",
-    "table",
-    "travel",
-    "less",
-    "morning",
-    "ten",
-    "simple",
-    "several",
-    "vowel",
-    "toward",
-    "war",
-    "lay",
-    "against",
-    "pattern",
-    "slow",
-    "center",
-    "love",
-    "person",
-    "money",
-    "serve",
-    "appear",
-    "road",
-    "map",
-    "rain",
-    "rule",
-    "govern",
-    "pull",
-    "cold",
-    "notice",
-    "voice",
-    "unit",
-    "power",
-    "town",
-    "fine",
-    "certain",
-    "fly",
-    "fall",
-    "lead",
-    "cry",
-    "dark",
-    "machine",
-    "note",
-    "wait",
-    "plan",
-    "figure",
-    "star",
-    "box",
-    "noun",
-    "field",
-    "rest",
-    "correct",
-    "able",
-    "pound",
-    "done",
-    "beauty",
-    "drive",
-    "stood",
-    "contain",
-    "front",
-    "teach",
-    "week",
-    "final",
-    "gave",
-    "green",
-    "oh",
-    "quick",
-    "develop",
-    "ocean",
-    "warm",
-    "free",
-    "minute",
-    "strong",
-    "special",
-    "mind",
-    "behind",
-    "clear",
-    "tail",
-    "produce",
-    "fact",
-    "street",
-    "inch",
-    "multiply",
-    "nothing",
-    "course",
-    "stay",
-    "wheel",
-    "full",
-    "force",
-    "blue",
-    "object",
-    "decide",
-    "surface",
-    "deep",
-    "moon",
-    "island",
-    "foot",
-    "system",
-    "busy",
-    "test",
-    "record",
-    "boat",
-    "common",
-    "gold",
-    "possible",
-    "plane",
-    "stead",
-    "dry",
-    "wonder",
-    "laugh",
-    "thousand",
-    "ago",
-    "ran",
-    "check",
-    "game",
-    "shape",
-    "equate",
-    "hot",
-    "miss",
-    "brought",
-    "heat",
-    "snow",
-    "tire",
-    "bring",
-    "yes",
-    "distant",
-    "fill",
-    "east",
-    "paint",
-    "language",
-    "among",
-    "grand",
-    "ball",
-    "yet",
-    "wave",
-    "drop",
-    "heart",
-    "am",
-    "present",
-    "heavy",
-    "dance",
-    "engine",
-    "position",
-    "arm",
-    "wide",
-    "sail",
-    "material",
-    "size",
-    "vary",
-    "settle",
-    "speak",
-    "weight",
-    "general",
-    "ice",
-    "matter",
-    "circle",
-    "pair",
-    "include",
-    "divide",
-    "syllable",
-    "felt",
-    "perhaps",
-    "pick",
-    "sudden",
-    "count",
-    "square",
-    "reason",
-    "length",
-    "represent",
-    "art",
-    "subject",
-    "region",
-    "energy",
-    "hunt",
-    "probable",
-    "bed",
-    "brother",
-    "egg",
-    "ride",
-    "cell",
-    "believe",
-    "fraction",
-    "forest",
-    "sit",
-    "race",
-    "window",
-    "store",
-    "summer",
-    "train",
-    "sleep",
-    "prove",
-    "lone",
-    "leg",
-    "exercise",
-    "wall",
-    "catch",
-    "mount",
-    "wish",
-    "sky",
-    "board",
-    "joy",
-    "winter",
-    "sat",
-    "written",
-    "wild",
-    "instrument",
-    "kept",
-    "glass",
-    "grass",
-    "cow",
-    "job",
-    "edge",
-    "sign",
-    "visit",
-    "past",
-    "soft",
-    "fun",
-    "bright",
-    "gas",
-    "weather",
-    "month",
-    "million",
-    "bear",
-    "finish",
-    "happy",
-    "hope",
-    "flower",
-    "clothes",
-    "strange",
-    "gone",
-    "jump",
-    "baby",
-    "eight",
-    "village",
-    "meet",
-    "root",
-    "buy",
-    "raise",
-    "solve",
-    "metal",
-    "whether",
-    "push",
-    "seven",
-    "paragraph",
-    "third",
-    "shall",
-    "held",
-    "hair",
-    "describe",
-    "cook",
-    "floor",
-    "either",
-    "result",
-    "burn",
-    "hill",
-    "safe",
-    "cat",
-    "century",
-    "consider",
-    "type",
-    "law",
-    "bit",
-    "coast",
-    "copy",
-    "phrase",
-    "silent",
-    "tall",
-    "sand",
-    "soil",
-    "roll",
-    "temperature",
-    "finger",
-    "industry",
-    "value",
-    "fight",
-    "lie",
-    "beat",
-    "excite",
-    "natural",
-    "view",
-    "sense",
-    "ear",
-    "else",
-    "quite",
-    "broke",
-    "case",
-    "middle",
-    "kill",
-    "son",
-    "lake",
-    "moment",
-    "scale",
-    "loud",
-    "spring",
-    "observe",
-    "child",
-    "straight",
-    "consonant",
-    "nation",
-    "dictionary",
-    "milk",
-    "speed",
-    "method",
-    "organ",
-    "pay",
-    "age",
-    "section",
-    "dress",
-    "cloud",
-    "surprise",
-    "quiet",
-    "stone",
-    "tiny",
-    "climb",
-    "cool",
-    "design",
-    "poor",
-    "lot",
-    "experiment",
-    "bottom",
-    "key",
-    "iron",
-    "single",
-    "stick",
-    "flat",
-    "twenty",
-    "skin",
-    "smile",
-    "crease",
-    "hole",
-    "trade",
-    "melody",
-    "trip",
-    "office",
-    "receive",
-    "row",
-    "mouth",
-    "exact",
-    "symbol",
-    "die",
-    "least",
-    "trouble",
-    "shout",
-    "except",
-    "wrote",
-    "seed",
-    "tone",
-    "join",
-    "suggest",
-    "clean",
-    "break",
-    "lady",
-    "yard",
-    "rise",
-    "bad",
-    "blow",
-    "oil",
-    "blood",
-    "touch",
-    "grew",
-    "cent",
-    "mix",
-    "team",
-    "wire",
-    "cost",
-    "lost",
-    "brown",
-    "wear",
-    "garden",
-    "equal",
-    "sent",
-    "choose",
-    "fell",
-    "fit",
-    "flow",
-    "fair",
-    "bank",
-    "collect",
-    "save",
-    "control",
-    "decimal",
-    "gentle",
-    "woman",
-    "captain",
-    "practice",
-    "separate",
-    "difficult",
-    "doctor",
-    "please",
-    "protect",
-    "noon",
-    "whose",
-    "locate",
-    "ring",
-    "character",
-    "insect",
-    "caught",
-    "period",
-    "indicate",
-    "radio",
-    "spoke",
-    "atom",
-    "human",
-    "history",
-    "effect",
-    "electric",
-    "expect",
-    "crop",
-    "modern",
-    "element",
-    "hit",
-    "student",
-    "corner",
-    "party",
-    "supply",
-    "bone",
-    "rail",
-    "imagine",
-    "provide",
-    "agree",
-    "thus",
-    "capital",
-    "won't",
-    "chair",
-    "danger",
-    "fruit",
-    "rich",
-    "thick",
-    "soldier",
-    "process",
-    "operate",
-    "guess",
-    "necessary",
-    "sharp",
-    "wing",
-    "create",
-    "neighbor",
-    "wash",
-    "bat",
-    "rather",
-    "crowd",
-    "corn",
-    "compare",
-    "poem",
-    "string",
-    "bell",
-    "depend",
-    "meat",
-    "rub",
-    "tube",
-    "famous",
-    "dollar",
-    "stream",
-    "fear",
-    "sight",
-    "thin",
-    "triangle",
-    "planet",
-    "hurry",
-    "chief",
-    "colony",
-    "clock",
-    "mine",
-    "tie",
-    "enter",
-    "major",
-    "fresh",
-    "search",
-    "send",
-    "yellow",
-    "gun",
-    "allow",
-    "print",
-    "dead",
-    "spot",
-    "desert",
-    "suit",
-    "current",
-    "lift",
-    "rose",
-    "continue",
-    "block",
-    "chart",
-    "hat",
-    "sell",
-    "success",
-    "company",
-    "subtract",
-    "event",
-    "particular",
-    "deal",
-    "swim",
-    "term",
-    "opposite",
-    "wife",
-    "shoe",
-    "shoulder",
-    "spread",
-    "arrange",
-    "camp",
-    "invent",
-    "cotton",
-    "born",
-    "determine",
-    "quart",
-    "nine",
-    "truck",
-    "noise",
-    "level",
-    "chance",
-    "gather",
-    "shop",
-    "stretch",
-    "throw",
-    "shine",
-    "property",
-    "column",
-    "molecule",
-    "select",
-    "wrong",
-    "gray",
-    "repeat",
-    "require",
-    "broad",
-    "prepare",
-    "salt",
-    "nose",
-    "plural",
-    "anger",
-    "claim",
-    "continent",
-    "oxygen",
-    "sugar",
-    "death",
-    "pretty",
-    "skill",
-    "women",
-    "season",
-    "solution",
-    "magnet",
-    "silver",
-    "thank",
-    "branch",
-    "match",
-    "suffix",
-    "especially",
-    "fig",
-    "afraid",
-    "huge",
-    "sister",
-    "steel",
-    "discuss",
-    "forward",
-    "similar",
-    "guide",
-    "experience",
-    "score",
-    "apple",
-    "bought",
-    "led",
-    "pitch",
-    "coat",
-    "mass",
-    "card",
-    "band",
-    "rope",
-    "slip",
-    "win",
-    "dream",
-    "evening",
-    "condition",
-    "feed",
-    "tool",
-    "total",
-    "basic",
-    "smell",
-    "valley",
-    "nor",
-    "double",
-    "seat",
-    "arrive",
-    "master",
-    "track",
-    "parent",
-    "shore",
-    "division",
-    "sheet",
-    "substance",
-    "favor",
-    "connect",
-    "post",
-    "spend",
-    "chord",
-    "fat",
-    "glad",
-    "original",
-    "share",
-    "station",
-    "dad",
-    "bread",
-    "charge",
-    "proper",
-    "bar",
-    "offer",
-    "segment",
-    "slave",
-    "duck",
-    "instant",
-    "market",
-    "degree",
-    "populate",
-    "chick",
-    "dear",
-    "enemy",
-    "reply",
-    "drink",
-    "occur",
-    "support",
-    "speech",
-    "nature",
-    "range",
-    "steam",
-    "motion",
-    "path",
-    "liquid",
-    "log",
-    "meant",
-    "quotient",
-    "teeth",
-    "shell",
-    "neck",
+    pub fn load(&'static self) -> Rc<dyn WordSource> {
+        Rc::new(WordList {
+            name: self.name.to_owned(),
+            builtin: Some(self),
+            words: self
+                .text
+                .lines()
+                .take(self.size)
+                .map(str::to_owned)
+                .collect(),
+        })
+    }
+}
+
+/// The 200 most common English words, and the 1000 most common
+pub static BUILTINS: [Builtin; 2] = [
+    Builtin {
+        name: "en",
+        size: 200,
+        text: include_str!("words/en.txt"),
+    },
+    Builtin {
+        name: "en1k",
+        size: 1000,
+        text: include_str!("words/en1k.txt"),
+    },
 ];
+
+/// Look up a builtin word list by name
+pub fn builtin(name: &str) -> Result<Rc<dyn WordSource>, String> {
+    match BUILTINS.iter().find(|b| b.name == name) {
+        Some(b) => Ok(b.load()),
+        None => Err(format!(
+            "unknown dictionary '{}', expected one of: {}",
+            name,
+            BUILTINS
+                .iter()
+                .map(|b| b.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// A word list loaded from a file at startup
+#[derive(Debug)]
+pub struct WordList {
+    name: String,
+    builtin: Option<&'static Builtin>,
+    words: Vec<String>,
+}
+
+impl WordSource for WordList {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn len(&self) -> usize {
+        self.words.len()
+    }
+    fn word(&self, i: usize) -> &str {
+        &self.words[i]
+    }
+    fn builtin(&self) -> Option<&'static Builtin> {
+        self.builtin
+    }
+}
+
+/// (line, text) pairs
+type Lines = Vec<(usize, String)>;
+
+#[derive(Debug)]
+pub enum WordListError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// (line, problem) for every bad entry
+    Invalid(Lines),
+    Empty,
+}
+
+impl Display for WordListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordListError::Io(e) => write!(f, "{}", e),
+            WordListError::Json(e) => write!(f, "invalid JSON: {}", e),
+            WordListError::Invalid(errors) => {
+                write!(f, "invalid entries")?;
+                for (line, problem) in errors {
+                    write!(f, "\n  line {}: {}", line, problem)?;
+                }
+                Ok(())
+            }
+            WordListError::Empty => write!(f, "the word list is empty"),
+        }
+    }
+}
+
+impl std::error::Error for WordListError {}
+
+impl WordList {
+    /// Load a list of words from a file, either one word per line or a JSON array of strings
+    pub fn load(path: &Path) -> Result<WordList, WordListError> {
+        let text = std::fs::read_to_string(path).map_err(WordListError::Io)?;
+        let (entries, mut errors) = if text.trim_start().starts_with('[') {
+            Self::parse_json(&text)?
+        } else {
+            let entries = text
+                .lines()
+                .enumerate()
+                .map(|(i, word)| (i + 1, word.to_owned()))
+                .collect();
+            (entries, Vec::new())
+        };
+
+        errors.extend(entries.iter().filter_map(|(line, word)| {
+            if word.is_empty() {
+                Some((*line, "empty entry".to_owned()))
+            } else if word.contains(char::is_whitespace) {
+                Some((*line, format!("{:?} contains whitespace", word)))
+            } else {
+                None
+            }
+        }));
+        if !errors.is_empty() {
+            errors.sort_by_key(|&(line, _)| line);
+            return Err(WordListError::Invalid(errors));
+        }
+        if entries.is_empty() {
+            return Err(WordListError::Empty);
+        }
+
+        Ok(WordList {
+            name: path
+                .file_stem()
+                .map_or_else(|| "custom".into(), |s| s.to_string_lossy().into_owned()),
+            builtin: None,
+            words: entries.into_iter().map(|(_, word)| word).collect(),
+        })
+    }
+
+    /// Parse a JSON array of strings, keeping the line each entry starts on.
+    /// Entries that are not strings are returned separately as errors
+    fn parse_json(text: &str) -> Result<(Lines, Lines), WordListError> {
+        let raw: Vec<&RawValue> = serde_json::from_str(text).map_err(WordListError::Json)?;
+        let mut errors = Vec::new();
+        let entries = raw
+            .into_iter()
+            .filter_map(|raw| {
+                let offset = raw.get().as_ptr() as usize - text.as_ptr() as usize;
+                let line = 1 + text[..offset].matches('\n').count();
+                match serde_json::from_str(raw.get()) {
+                    Ok(word) => Some((line, word)),
+                    Err(_) => {
+                        errors.push((line, format!("{} is not a string", raw.get())));
+                        None
+                    }
+                }
+            })
+            .collect();
+        Ok((entries, errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Load `text` as the word list `name`
+    fn load(name: &str, text: &str) -> Result<WordList, WordListError> {
+        let dir = std::env::temp_dir().join(format!("shelltyper-dict-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        let list = WordList::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        list
+    }
+
+    fn invalid(result: Result<WordList, WordListError>) -> Lines {
+        match result {
+            Err(WordListError::Invalid(errors)) => errors,
+            other => panic!("expected invalid entries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn loads_one_word_per_line() {
+        let list = load("common.txt", "the\nof\r\nand\n").unwrap();
+        assert_eq!(list.name(), "common");
+        assert_eq!(list.words, ["the", "of", "and"]);
+        assert!(list.builtin().is_none());
+    }
+
+    #[test]
+    fn loads_a_json_array() {
+        let list = load("common.json", "  [\"the\", \"of\",\n\"and\"]").unwrap();
+        assert_eq!(list.name(), "common");
+        assert_eq!(list.words, ["the", "of", "and"]);
+    }
+
+    #[test]
+    fn reports_the_line_of_every_bad_entry() {
+        assert_eq!(
+            invalid(load("lines.txt", "the\n\nof and\nthe end")),
+            [
+                (2, "empty entry".to_owned()),
+                (3, "\"of and\" contains whitespace".to_owned()),
+                (4, "\"the end\" contains whitespace".to_owned()),
+            ]
+        );
+        assert_eq!(
+            invalid(load("entries.json", "[\"the\",\n  5, \"a b\",\n\n  \"\"]")),
+            [
+                (2, "5 is not a string".to_owned()),
+                (2, "\"a b\" contains whitespace".to_owned()),
+                (4, "empty entry".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_empty_lists_and_broken_json() {
+        assert!(matches!(load("empty.txt", ""), Err(WordListError::Empty)));
+        assert!(matches!(
+            load("empty.json", "[]"),
+            Err(WordListError::Empty)
+        ));
+        assert!(matches!(
+            load("broken.json", "[\"the\""),
+            Err(WordListError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn builtins_are_known_as_builtin() {
+        let en = builtin("en").unwrap();
+        assert_eq!((en.name(), en.len()), ("en", 200));
+        assert!(en.builtin().is_some());
+        assert!(builtin("en10k").is_err());
+    }
+
+    #[test]
+    fn builtin_lists_hold_single_words() {
+        for builtin in BUILTINS.iter() {
+            let list = builtin.load();
+            let words: Vec<_> = (0..list.len()).map(|i| list.word(i)).collect();
+            assert!(words
+                .iter()
+                .all(|w| !w.is_empty() && !w.contains(char::is_whitespace)));
+            let unique: std::collections::HashSet<_> = words.iter().collect();
+            assert_eq!(unique.len(), words.len(), "{}", list.name());
+        }
+    }
+}
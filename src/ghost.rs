@@ -0,0 +1,238 @@
+use crate::history::{Record, TestKind};
+use crate::keys::{Command, KeyMap};
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::time::Duration;
+
+/// The best previous run of a kind of test, replayed as a second cursor during a test
+#[derive(Debug)]
+pub struct Ghost {
+    kind: TestKind,
+    wpm: f64,
+    /// (microseconds since the start, words completed, characters typed into the next word)
+    /// after every accepted key
+    positions: Vec<(u64, usize, usize)>,
+    wpm_history: Vec<(f64, f64)>,
+}
+
+impl Ghost {
+    /// Follow the keystrokes of a recorded test, if it kept them, with `keys` telling
+    /// which of them deleted a word
+    pub fn new(record: &Record, keys: &KeyMap) -> Option<Ghost> {
+        if record.keystrokes.is_empty() {
+            return None;
+        }
+        // Characters typed into every word so far, backspace can go back into earlier ones
+        let mut words = vec![0];
+        let positions = record
+            .keystrokes
+            .iter()
+            .filter(|k| k.accepted)
+            .map(|k| {
+                let held = k
+                    .key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+                if keys.command(k.key, true) == Some(Command::DeleteWord) {
+                    if words.len() > 1 && words.last() == Some(&0) {
+                        words.pop();
+                    }
+                    *words.last_mut().unwrap() = 0;
+                } else if !held {
+                    match k.key.code {
+                        KeyCode::Char(' ') | KeyCode::Right | KeyCode::Enter => words.push(0),
+                        KeyCode::Char(_) => *words.last_mut().unwrap() += 1,
+                        KeyCode::Backspace => match words.last_mut().unwrap() {
+                            0 => {
+                                words.pop();
+                            }
+                            chars => *chars -= 1,
+                        },
+                        _ => {}
+                    }
+                }
+                (k.time, words.len() - 1, *words.last().unwrap())
+            })
+            .collect();
+        Some(Ghost {
+            kind: record.kind(),
+            wpm: record.wpm,
+            positions,
+            wpm_history: record.wpm_history.clone(),
+        })
+    }
+
+    /// The fastest complete run of every kind of test that kept its keystrokes
+    pub fn bests(records: &[Record], keys: &KeyMap) -> Vec<Ghost> {
+        let mut bests: Vec<(TestKind, &Record)> = Vec::new();
+        for record in records
+            .iter()
+            .filter(|r| r.completed && !r.keystrokes.is_empty())
+        {
+            let kind = record.kind();
+            match bests.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, best)) if best.wpm >= record.wpm => {}
+                Some((_, best)) => *best = record,
+                None => bests.push((kind, record)),
+            }
+        }
+        bests
+            .into_iter()
+            .filter_map(|(_, record)| Ghost::new(record, keys))
+            .collect()
+    }
+
+    pub fn kind(&self) -> &TestKind {
+        &self.kind
+    }
+
+    pub fn wpm(&self) -> f64 {
+        self.wpm
+    }
+
+    pub fn wpm_history(&self) -> &[(f64, f64)] {
+        &self.wpm_history
+    }
+
+    /// (words completed, characters typed into the next word) `elapsed` into the test
+    pub fn position(&self, elapsed: Duration) -> (usize, usize) {
+        let time = elapsed.as_micros() as u64;
+        let reached = self.positions.partition_point(|&(t, _, _)| t <= time);
+        match reached.checked_sub(1) {
+            Some(i) => (self.positions[i].1, self.positions[i].2),
+            None => (0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{self, Keystroke};
+    use crate::modifiers::Modifiers;
+    use crate::sampling::Sampling;
+    use crate::TargetStringType;
+    use crossterm::event::KeyEvent;
+
+    fn record(mode: TargetStringType, wpm: f64, keys: &[(KeyCode, KeyModifiers)]) -> Record {
+        Record {
+            version: history::VERSION,
+            timestamp: 0,
+            mode,
+            dict: None,
+            source: None,
+            seed: None,
+            sampling: Default::default(),
+            modifiers: Default::default(),
+            correction: Default::default(),
+            wpm,
+            accuracy: 100.,
+            raw_wpm: wpm,
+            net_wpm: wpm,
+            char_accuracy: 100.,
+            consistency: 100.,
+            correct: 1,
+            duration: keys.len() as f64,
+            completed: true,
+            wpm_history: Vec::new(),
+            accuracy_history: Vec::new(),
+            keystrokes: keys
+                .iter()
+                .enumerate()
+                .map(|(i, &(code, modifiers))| Keystroke {
+                    time: i as u64 + 1,
+                    key: KeyEvent::new(code, modifiers),
+                    accepted: true,
+                })
+                .collect(),
+            target: String::new(),
+        }
+    }
+
+    fn typed(text: &str) -> Vec<(KeyCode, KeyModifiers)> {
+        text.chars()
+            .map(|c| (KeyCode::Char(c), KeyModifiers::NONE))
+            .collect()
+    }
+
+    /// Where the ghost is after every key
+    fn positions(keys: &[(KeyCode, KeyModifiers)]) -> Vec<(usize, usize)> {
+        let words = TargetStringType::Words(5);
+        let ghost = Ghost::new(&record(words, 60., keys), &KeyMap::default()).unwrap();
+        (1..=keys.len() as u64)
+            .map(|t| ghost.position(Duration::from_micros(t)))
+            .collect()
+    }
+
+    #[test]
+    fn follows_typing_and_backspace_across_words() {
+        let mut keys = typed("ab c");
+        keys.extend([(KeyCode::Backspace, KeyModifiers::NONE); 2]);
+        keys.extend(typed("x"));
+        assert_eq!(
+            positions(&keys),
+            [(0, 1), (0, 2), (1, 0), (1, 1), (1, 0), (0, 2), (0, 3)]
+        );
+    }
+
+    #[test]
+    fn deleting_a_word_goes_back_to_its_start() {
+        let mut keys = typed("ab c");
+        keys.push((KeyCode::Char('w'), KeyModifiers::CONTROL));
+        keys.push((KeyCode::Char('w'), KeyModifiers::CONTROL));
+        keys.push((KeyCode::Char('x'), KeyModifiers::ALT));
+        assert_eq!(
+            positions(&keys),
+            [(0, 1), (0, 2), (1, 0), (1, 1), (1, 0), (0, 0), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn starts_before_the_first_key() {
+        let words = TargetStringType::Words(5);
+        let ghost = Ghost::new(&record(words, 60., &typed("a")), &KeyMap::default()).unwrap();
+        assert_eq!(ghost.position(Duration::from_micros(0)), (0, 0));
+        assert!(Ghost::new(&record(words, 60., &[]), &KeyMap::default()).is_none());
+    }
+
+    #[test]
+    fn bests_are_the_fastest_complete_run_of_each_kind_of_test() {
+        let (words, timed) = (TargetStringType::Words(5), TargetStringType::Timed(15));
+        let punctuated = Modifiers {
+            punctuation: Some(true),
+            numbers: None,
+        };
+        let run = |mode, dict: &str, modifiers, wpm, completed| {
+            let mut record = record(mode, wpm, &typed("a"));
+            record.dict = Some(dict.to_owned());
+            record.modifiers = modifiers;
+            record.completed = completed;
+            record
+        };
+        let none = Modifiers::default();
+        let mut unkept = run(words, "en", none, 90., true);
+        unkept.keystrokes.clear();
+        let records = [
+            run(words, "en", none, 50., true),
+            run(words, "en", none, 70., true),
+            unkept,
+            run(words, "en", none, 200., false),
+            run(timed, "en", none, 40., true),
+            run(words, "en1k", none, 100., true),
+            run(words, "en", punctuated, 80., true),
+            run(words, "en", none, 60., true),
+        ];
+        let bests = Ghost::bests(&records, &KeyMap::default());
+        let bests: Vec<_> = bests.iter().map(|g| (g.kind().clone(), g.wpm())).collect();
+        let kind =
+            |mode, dict, modifiers| TestKind::new(mode, Some(dict), Sampling::Uniform, modifiers);
+        assert_eq!(
+            bests,
+            [
+                (kind(words, "en", none), 70.),
+                (kind(timed, "en", none), 40.),
+                (kind(words, "en1k", none), 100.),
+                (kind(words, "en", punctuated), 80.),
+            ]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use crate::theme::Theme;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use tui::style::Style;
+use tui::text::{Span, Spans};
+
+/// Unshifted keys of a US keyboard, with how far each row is indented
+const ROWS: [(usize, &str); 4] = [
+    (0, "1234567890-="),
+    (2, "qwertyuiop[]"),
+    (3, "asdfghjkl;'"),
+    (4, "zxcvbnm,./"),
+];
+const SHIFTED: &str = "!@#$%^&*()_+QWERTYUIOP{}ASDFGHJKL:\"ZXCVBNM<>?";
+const UNSHIFTED: &str = "1234567890-=qwertyuiop[]asdfghjkl;'zxcvbnm,./";
+
+/// How often one character was expected, and what was typed when it was missed
+#[derive(Debug, Default)]
+struct CharStats {
+    expected: usize,
+    errors: usize,
+    typed_instead: BTreeMap<char, usize>,
+}
+
+/// Per character accounting of the keys pressed during a test
+#[derive(Debug, Default)]
+pub struct KeyErrors {
+    chars: BTreeMap<char, CharStats>,
+}
+
+impl KeyErrors {
+    pub fn clear(&mut self) {
+        self.chars.clear();
+    }
+
+    /// `typed` was pressed where `expected` should have been
+    pub fn record(&mut self, expected: char, typed: char) {
+        let stats = self.chars.entry(expected).or_default();
+        stats.expected += 1;
+        if typed != expected {
+            stats.errors += 1;
+            *stats.typed_instead.entry(typed).or_default() += 1;
+        }
+    }
+
+    /// Error rate of everything typed with a physical key
+    fn key_error_rate(&self, key: char) -> Option<f64> {
+        let (expected, errors) = self
+            .chars
+            .iter()
+            .filter(|&(&c, _)| key_of(c) == key)
+            .fold((0, 0), |(exp, err), (_, s)| {
+                (exp + s.expected, err + s.errors)
+            });
+        (expected > 0).then(|| errors as f64 / expected as f64)
+    }
+
+    /// The most common (expected, typed, count) mistakes
+    fn worst(&self, n: usize) -> Vec<(char, char, usize)> {
+        self.chars
+            .iter()
+            .flat_map(|(&exp, s)| s.typed_instead.iter().map(move |(&t, &n)| (exp, t, n)))
+            .sorted_by_key(|&(_, _, count)| std::cmp::Reverse(count))
+            .take(n)
+            .collect()
+    }
+
+    /// The keyboard coloured by error rate, with the most common mistakes beside it
+    pub fn lines(&self, theme: &Theme) -> Vec<Spans<'static>> {
+        let worst = self.worst(ROWS.len() + 1);
+        let mistake = |i: usize| {
+            worst.get(i).map_or_else(String::new, |&(exp, typed, n)| {
+                format!("   {} -> {} x{}", show(exp), show(typed), n)
+            })
+        };
+        let width = ROWS
+            .iter()
+            .map(|(indent, keys)| indent + 3 * keys.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines: Vec<_> = ROWS
+            .iter()
+            .enumerate()
+            .map(|(i, &(indent, keys))| {
+                let mut spans = vec![Span::raw(" ".repeat(indent))];
+                spans.extend(
+                    keys.chars()
+                        .map(|k| Span::styled(format!(" {} ", k), self.style(k, theme))),
+                );
+                spans.push(Span::raw(" ".repeat(width - indent - 3 * keys.len())));
+                spans.push(Span::raw(mistake(i)));
+                Spans::from(spans)
+            })
+            .collect();
+        lines.push(Spans::from(vec![
+            Span::raw(" ".repeat(6)),
+            Span::styled(format!("{:^24}", "space"), self.style(' ', theme)),
+            Span::raw(" ".repeat(width - 30)),
+            Span::raw(mistake(ROWS.len())),
+        ]));
+        lines
+    }
+
+    fn style(&self, key: char, theme: &Theme) -> Style {
+        let heat = match self.key_error_rate(key) {
+            None => 0,
+            Some(0.) => 1,
+            Some(rate) if rate < 0.05 => 2,
+            Some(rate) if rate < 0.15 => 3,
+            Some(_) => 4,
+        };
+        Style::default()
+            .fg(theme.highlight_text)
+            .bg(theme.heat[heat])
+    }
+}
+
+/// The key a character is typed with, ignoring shift
+fn key_of(c: char) -> char {
+    match SHIFTED.find(c) {
+        Some(i) => UNSHIFTED[i..].chars().next().unwrap(),
+        None => c.to_ascii_lowercase(),
+    }
+}
+
+/// Make whitespace visible in the list of mistakes
+fn show(c: char) -> String {
+    match c {
+        ' ' => "space".into(),
+        '\n' => "enter".into(),
+        c => c.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (expected, typed) pairs, `times` times each
+    fn errors(pairs: &[(char, char, usize)]) -> KeyErrors {
+        let mut errors = KeyErrors::default();
+        for &(expected, typed, times) in pairs {
+            (0..times).for_each(|_| errors.record(expected, typed));
+        }
+        errors
+    }
+
+    #[test]
+    fn shifted_characters_belong_to_their_key() {
+        assert_eq!(SHIFTED.len(), UNSHIFTED.len());
+        assert_eq!(key_of('Q'), 'q');
+        assert_eq!(key_of('?'), '/');
+        assert_eq!(key_of('!'), '1');
+        assert_eq!(key_of('"'), '\'');
+        assert_eq!(key_of('a'), 'a');
+        assert_eq!(key_of('é'), 'é');
+    }
+
+    #[test]
+    fn error_rate_adds_up_a_key_with_and_without_shift() {
+        let errors = errors(&[('a', 'a', 5), ('a', 's', 1), ('A', 'A', 3), ('A', 'a', 1)]);
+        assert_eq!(errors.key_error_rate('a'), Some(0.2));
+        // Typing a character by mistake doesn't count as it being expected
+        assert_eq!(errors.key_error_rate('s'), None);
+        assert_eq!(errors.key_error_rate('b'), None);
+    }
+
+    #[test]
+    fn worst_mistakes_come_first() {
+        let errors = errors(&[('t', 'y', 1), ('e', 'r', 3), ('a', 's', 2), ('e', 'e', 9)]);
+        assert_eq!(errors.worst(2), [('e', 'r', 3), ('a', 's', 2)]);
+        assert_eq!(errors.worst(10).len(), 3);
+    }
+
+    #[test]
+    fn keys_heat_up_with_their_error_rate() {
+        let theme = Theme::default();
+        let errors = errors(&[
+            ('a', 'a', 10),
+            ('s', 's', 99),
+            ('s', 'a', 1),
+            ('d', 'd', 9),
+            ('d', 's', 1),
+            ('f', 'd', 1),
+        ]);
+        for (key, heat) in [('g', 0), ('a', 1), ('s', 2), ('d', 3), ('f', 4)] {
+            assert_eq!(
+                errors.style(key, &theme).bg,
+                Some(theme.heat[heat]),
+                "{}",
+                key
+            );
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::modifiers::Modifiers;
+use crate::sampling::Sampling;
+use crate::{Correction, TargetStringType};
+use crossterm::event::KeyEvent;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the record format changes incompatibly
+pub const VERSION: u32 = 1;
+
+/// The results of one finished test, stored as a line of JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub version: u32,
+    /// Seconds since the unix epoch when the test finished
+    pub timestamp: u64,
+    pub mode: TargetStringType,
+    /// Word list the test was generated from, for word based tests
+    pub dict: Option<String>,
+    /// Where the quote came from, or the file name of a code test
+    pub source: Option<String>,
+    /// What the words or quote were randomly chosen with
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// How the words were picked from `dict`, which changes what `seed` gives
+    #[serde(default)]
+    pub sampling: Sampling,
+    /// Punctuation and numbers as they were turned on or off, unset for older records
+    #[serde(default)]
+    pub modifiers: Modifiers,
+    /// Which words backspace could go back into, which replays have to follow
+    #[serde(default)]
+    pub correction: Correction,
+    /// Correct words per minute
+    pub wpm: f64,
+    /// Percentage of words typed correctly
+    pub accuracy: f64,
+    /// Every character typed, divided by 5, per minute
+    #[serde(default)]
+    pub raw_wpm: f64,
+    /// Correct characters, divided by 5, per minute
+    #[serde(default)]
+    pub net_wpm: f64,
+    /// Percentage of keystrokes that were correct
+    #[serde(default)]
+    pub char_accuracy: f64,
+    /// 100% minus the coefficient of variation of the per second speed
+    #[serde(default)]
+    pub consistency: f64,
+    /// Words typed correctly
+    pub correct: usize,
+    /// Seconds spent typing
+    pub duration: f64,
+    /// Whether the test ran to the end, rather than being cut short with the end key.
+    /// Records from before this was kept count as complete
+    #[serde(default = "complete")]
+    pub completed: bool,
+    /// (progress, wpm)
+    pub wpm_history: Vec<(f64, f64)>,
+    /// (progress, accuracy)
+    pub accuracy_history: Vec<(f64, f64)>,
+    /// Every key pressed during the test
+    #[serde(default)]
+    pub keystrokes: Vec<Keystroke>,
+    /// The text of the test, as far as it was generated
+    #[serde(default)]
+    pub target: String,
+}
+
+fn complete() -> bool {
+    true
+}
+
+/// What a test was apart from its words. Only runs of the same kind race each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestKind {
+    mode: TargetStringType,
+    /// Word list of tests generated from one
+    dict: Option<String>,
+    sampling: Sampling,
+    /// (punctuation, numbers) as they applied to the test
+    modifiers: (bool, bool),
+}
+
+impl TestKind {
+    /// Leaves out what didn't change the words of a `mode` test
+    pub fn new(
+        mode: TargetStringType,
+        dict: Option<&str>,
+        sampling: Sampling,
+        modifiers: Modifiers,
+    ) -> TestKind {
+        let dict = dict.map(str::to_owned);
+        TestKind {
+            mode,
+            sampling: if dict.is_some() {
+                sampling
+            } else {
+                Sampling::Uniform
+            },
+            dict,
+            modifiers: match mode {
+                TargetStringType::Code => (false, false),
+                _ => modifiers.of(mode),
+            },
+        }
+    }
+}
+
+impl Record {
+    pub fn kind(&self) -> TestKind {
+        TestKind::new(
+            self.mode,
+            self.dict.as_deref(),
+            self.sampling,
+            self.modifiers,
+        )
+    }
+}
+
+/// A key pressed during a test
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Keystroke {
+    /// Microseconds since the test started
+    pub time: u64,
+    pub key: KeyEvent,
+    /// Whether the key changed the text typed so far. Rejected keys include a space
+    /// before anything was typed and backspacing into a finished word
+    pub accepted: bool,
+}
+
+/// `$XDG_DATA_HOME/shelltyper/history.jsonl`, falling back to `~/.local/share`
+pub fn default_path() -> Option<PathBuf> {
+    let data = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(data.join("shelltyper").join("history.jsonl"))
+}
+
+/// Add a record to the end of the history file, creating it if needed
+pub fn append(path: &Path, record: &Record) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())
+}
+
+/// Read every record from the history file. Lines that can't be read, for example ones
+/// written by a newer version, are reported on stderr and skipped
+pub fn load(path: &Path) -> io::Result<Vec<Record>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let records = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| match serde_json::from_str::<Record>(line) {
+            Ok(record) if record.version <= VERSION => Some(record),
+            Ok(record) => {
+                eprintln!(
+                    "{}:{}: skipping record with unknown version {}",
+                    path.display(),
+                    i + 1,
+                    record.version
+                );
+                None
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}:{}: skipping invalid record: {}",
+                    path.display(),
+                    i + 1,
+                    e
+                );
+                None
+            }
+        })
+        .collect();
+    Ok(records)
+}
@@ -0,0 +1,144 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::str::FromStr;
+
+/// What a key can be bound to, besides typing
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Start the running test over, or open the menu outside of a test
+    Restart,
+    /// End the running test, or start the next one after it
+    End,
+    /// Delete the word being typed, or the one before it if nothing has been typed
+    DeleteWord,
+    Quit,
+}
+
+/// A key, with whether ctrl or alt has to be held for it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// A character typed without ctrl or alt
+    fn is_typed(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && self.modifiers.is_empty()
+    }
+
+    fn matches(&self, event: KeyEvent) -> bool {
+        // Shift is part of the character typed, so it is ignored
+        let held = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        self.code == event.code && self.modifiers == held
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// A key name like `esc`, `f5` or `q`, optionally after `ctrl-` and `alt-`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut name = s;
+        loop {
+            let lower = name.to_lowercase();
+            if lower.starts_with("ctrl-") && name.len() > 5 {
+                modifiers |= KeyModifiers::CONTROL;
+            } else if lower.starts_with("alt-") && name.len() > 4 {
+                modifiers |= KeyModifiers::ALT;
+            } else {
+                break;
+            }
+            name = &name[name.find('-').unwrap() + 1..];
+        }
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "enter" | "return" => KeyCode::Enter,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                f => match f.strip_prefix('f').map(str::parse) {
+                    Some(Ok(n)) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", s)),
+                },
+            },
+        };
+        Ok(Key { code, modifiers })
+    }
+}
+
+/// Which keys run which commands
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Key, Command)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let key = |code| Key {
+            code,
+            modifiers: KeyModifiers::NONE,
+        };
+        let ctrl = |c| Key {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::CONTROL,
+        };
+        KeyMap {
+            bindings: vec![
+                (key(KeyCode::Esc), Command::Restart),
+                (key(KeyCode::Tab), Command::End),
+                // Not ctrl-h, which some terminals send for a plain backspace
+                (ctrl('w'), Command::DeleteWord),
+                (
+                    Key {
+                        code: KeyCode::Backspace,
+                        modifiers: KeyModifiers::CONTROL,
+                    },
+                    Command::DeleteWord,
+                ),
+                (
+                    Key {
+                        code: KeyCode::Backspace,
+                        modifiers: KeyModifiers::ALT,
+                    },
+                    Command::DeleteWord,
+                ),
+                (ctrl('c'), Command::Quit),
+                (ctrl('q'), Command::Quit),
+                (key(KeyCode::Char('q')), Command::Quit),
+            ],
+        }
+    }
+}
+
+impl KeyMap {
+    /// The command a key press runs, if any. While `typing`, keys bound to plain
+    /// characters type them instead
+    pub fn command(&self, event: KeyEvent, typing: bool) -> Option<Command> {
+        self.bindings
+            .iter()
+            .filter(|(key, _)| !(typing && key.is_typed()))
+            .find(|(key, _)| key.matches(event))
+            .map(|&(_, command)| command)
+    }
+
+    /// Run `command` with `keys` instead of the keys it had
+    pub fn bind(&mut self, command: Command, keys: &[Key]) {
+        self.bindings.retain(|&(_, c)| c != command);
+        self.bindings.extend(keys.iter().map(|&key| (key, command)));
+    }
+}
@@ -1,28 +1,68 @@
+mod challenge;
+mod clock;
+mod code;
+mod config;
 mod dict;
+mod ghost;
+mod heatmap;
+mod history;
+mod keys;
+mod menu;
+mod modifiers;
+mod quotes;
+mod replay;
+mod sampling;
+mod stats;
+mod theme;
+
+use challenge::Challenge;
+use clock::{Clock, SystemClock};
+use code::Code;
+use config::Config;
+use dict::{WordList, WordSource};
+use ghost::Ghost;
+use heatmap::KeyErrors;
+use history::{Keystroke, Record, TestKind};
+use keys::{Command, KeyMap};
+use menu::{Menu, MenuAction};
+use modifiers::{Modifiers, Punctuator};
+use quotes::Quote;
+use sampling::Sampling;
+use theme::Theme;
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent},
+    cursor::Show,
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent,
+        KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use itertools::{izip, Itertools};
-use rand::distributions::Uniform;
 use rand::prelude::Distribution;
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::convert::TryInto;
 use std::io::stdout;
 use std::ops::{Range, Rem};
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::mpsc::{self, Receiver};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tui::layout::{Alignment, Constraint, Layout, Rect};
-use tui::style::{Color, Modifier, Style};
+use tui::style::{Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::{
     self, Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, LineGauge, Paragraph, Wrap,
 };
 use tui::{backend::CrosstermBackend, Terminal};
 use tui::{symbols, Frame};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 type Backend = CrosstermBackend<std::io::Stdout>;
 
@@ -36,53 +76,309 @@ const HELP: &str = "\
 monkeytype in the shell
 
 Usage: shelltyper [OPTIONS]
+       shelltyper stats [--json]    Summarise past results, see `shelltyper stats --help`
+       shelltyper replay [FILE]     Watch a past test again, see `shelltyper replay --help`
 
     -t, --timed            SECONDS    Typing test with time limit
     -w, --words            NUM_WORDS  Typing test with fixed number of words
-      By default it is a test with 30 words
+    -q, --quote                       Type a whole quote
+    -c, --code             PATH       Type a source file, Enter starts a new line
+      By default it is a test with 30 words, press Esc before or after a test to pick another
+        --challenge        CODE       Start the test described by a challenge code, as
+                                      shown after every test
+        --correction       POLICY     Which finished words backspace can go back into:
+                                      never, incorrect or always [default: incorrect]
+    -s, --seed             NUMBER     Generate the same test every time from this seed
+    -d, --dict             NAME       Word list to use: en, en1k [default: en]
+        --sampling         MODE       How often words come up: uniform, zipf (as often as
+                                      in real text), top<N> (only the N most common, like
+                                      top100) or hard (long and rare) [default: uniform]
+        --punctuation                 Add capitals and punctuation to the words
+        --numbers                     Mix numbers in with the words
+        --no-punctuation              Take the punctuation and capitals out of quotes
+        --no-numbers                  Take the numbers out of quotes
+        --wordlist         PATH       Load the word list from a file instead, with one word
+                                      per line or as a JSON array of strings
+        --quotes           PATH       Load quotes from a JSON array of
+                                      {\"text\": ..., \"source\": ...} objects
+        --no-history                  Don't save the results of finished tests to
+                                      $XDG_DATA_HOME/shelltyper/history.jsonl
+        --theme            NAME       Colors to use: dark, light, solarized, high-contrast,
+                                      monochrome [default: dark]
+        --ghost                       Race a second cursor that follows your best previous
+                                      run of the same mode
     -H, --chart-height     ROWS       Height of the Chart widget [default: 10]
     -M, --chart-max-wpm    NUMBER     Upper bound of WPM in the Chart [default: 150]
     -N, --chart-min-wpm    NUMBER     Lower bound of WPM in the Chart [default: 50]
+
+Esc restarts a test, Tab ends it, Ctrl-W or Ctrl-Backspace delete a word, and Ctrl-C or
+Ctrl-Q quit, as does q after a test or in the menu. Defaults for these, colors and key
+bindings are read from $XDG_CONFIG_HOME/shelltyper/config.toml, see the README
 ";
 #[derive(Debug)]
 struct Args {
     target_type: TargetStringType,
+    dict: Rc<dyn WordSource>,
+    quotes: Vec<Quote>,
+    /// Whether `quotes` came from a file rather than the bundled ones
+    custom_quotes: bool,
+    code: Option<Code>,
+    sampling: Sampling,
+    modifiers: Modifiers,
+    correction: Correction,
+    /// Seed every test is generated from, instead of a random one
+    seed: Option<u64>,
+    /// Where finished tests are saved, if anywhere
+    history: Option<PathBuf>,
+    /// Race against the best previous run of the same mode
+    ghost: bool,
     chart_max_wpm: usize,
     chart_min_wpm: usize,
     chart_height: usize,
+    theme: Theme,
+    keys: KeyMap,
 }
 impl Default for Args {
     fn default() -> Self {
         Args {
             target_type: TargetStringType::default(),
+            dict: dict::BUILTINS[0].load(),
+            quotes: quotes::bundled(),
+            custom_quotes: false,
+            code: None,
+            sampling: Sampling::default(),
+            modifiers: Modifiers::default(),
+            correction: Correction::default(),
+            seed: None,
+            history: history::default_path(),
+            ghost: false,
             chart_max_wpm: 150,
             chart_height: 10,
             chart_min_wpm: 50,
+            theme: Theme::default(),
+            keys: KeyMap::default(),
         }
     }
 }
 impl Args {
+    /// The defaults, with the settings from the config file applied.
+    /// A theme given on the command line replaces the one in the file
+    fn configured(config: Config, theme: Option<Theme>) -> Result<Args, String> {
+        let mut args = Args::default();
+        args.theme = match (theme, config.theme) {
+            (Some(theme), _) => theme,
+            (None, Some(name)) => Theme::builtin(&name).map_err(|e| format!("theme: {}", e))?,
+            (None, None) => args.theme,
+        };
+        let test = config.test;
+        args.target_type = match (test.mode, test.length) {
+            (Some(config::Mode::Timed), length) => TargetStringType::Timed(length.unwrap_or(15)),
+            (Some(config::Mode::Words), length) => TargetStringType::Words(length.unwrap_or(30)),
+            (Some(config::Mode::Quote), _) => TargetStringType::Quote,
+            (None, Some(length)) => match args.target_type {
+                TargetStringType::Timed(_) => TargetStringType::Timed(length),
+                TargetStringType::Words(_) => TargetStringType::Words(length),
+                other => other,
+            },
+            (None, None) => args.target_type,
+        };
+        if let Some(name) = test.dict {
+            args.dict = dict::builtin(&name).map_err(|e| format!("test.dict: {}", e))?;
+        }
+        args.sampling = test.sampling.unwrap_or(args.sampling);
+        args.modifiers = Modifiers {
+            punctuation: test.punctuation,
+            numbers: test.numbers,
+        };
+        args.correction = test.correction.unwrap_or(args.correction);
+        if test.history == Some(false) {
+            args.history = None;
+        }
+        args.ghost = test.ghost.unwrap_or(args.ghost);
+
+        let chart = config.chart;
+        args.chart_height = chart.height.unwrap_or(args.chart_height);
+        args.chart_min_wpm = chart.min_wpm.unwrap_or(args.chart_min_wpm);
+        args.chart_max_wpm = chart.max_wpm.unwrap_or(args.chart_max_wpm);
+
+        let (colors, theme) = (config.colors, &mut args.theme);
+        for (color, set) in [
+            (&mut theme.background, colors.background),
+            (&mut theme.text, colors.text),
+            (&mut theme.border, colors.border),
+            (&mut theme.completed, colors.completed),
+            (&mut theme.correct, colors.correct),
+            (&mut theme.wrong, colors.wrong),
+            (&mut theme.wrong_background, colors.wrong_background),
+            (&mut theme.untyped, colors.untyped),
+            (&mut theme.current, colors.current),
+            (&mut theme.ghost, colors.ghost),
+            (&mut theme.highlight_text, colors.highlight_text),
+            (&mut theme.ready, colors.ready),
+            (&mut theme.running, colors.running),
+            (&mut theme.done, colors.done),
+            (&mut theme.menu, colors.menu),
+            (&mut theme.wpm, colors.wpm),
+            (&mut theme.accuracy, colors.accuracy),
+            (&mut theme.progress, colors.progress),
+        ] {
+            *color = set.unwrap_or(*color);
+        }
+
+        let keys = config.keys;
+        for (command, bound) in [
+            (Command::Restart, keys.restart),
+            (Command::End, keys.end),
+            (Command::DeleteWord, keys.delete_word),
+            (Command::Quit, keys.quit),
+        ] {
+            if let Some(bound) = bound {
+                args.keys.bind(command, &bound);
+            }
+        }
+        Ok(args)
+    }
+
     fn parse_env() -> Args {
         let mut pargs = pico_args::Arguments::from_env();
 
+        match pargs
+            .subcommand()
+            .unwrap_or_else(|e| exit_with_error(e))
+            .as_deref()
+        {
+            Some("stats") => match stats::run(pargs) {
+                Ok(()) => std::process::exit(0),
+                Err(e) => exit_with_error(e),
+            },
+            Some("replay") => match replay::run(pargs) {
+                Ok(()) => std::process::exit(0),
+                Err(e) => exit_with_error(e),
+            },
+            Some(cmd) => exit_with_error(format!("unknown command '{}'", cmd)),
+            None => {}
+        }
+
         if pargs.contains(["-h", "--help"]) {
             print!("{}", HELP);
             std::process::exit(0);
         }
 
-        let dargs = Self::default();
+        let theme = pargs
+            .opt_value_from_fn("--theme", Theme::builtin)
+            .unwrap_or_else(|e| exit_with_error(e));
+        let dargs = match config::default_path() {
+            Some(path) => config::load(&path)
+                .and_then(|config| Self::configured(config, theme))
+                .unwrap_or_else(|e| exit_with_error(format!("{}: {}", path.display(), e))),
+            None => Args {
+                theme: theme.unwrap_or_default(),
+                ..Self::default()
+            },
+        };
+
+        let challenge = pargs
+            .opt_value_from_str::<_, Challenge>("--challenge")
+            .unwrap_or_else(|e| exit_with_error(e));
+        let challenge_dict = challenge.as_ref().map(|c| match dict::builtin(&c.dict) {
+            Ok(dict) => dict,
+            Err(e) => exit_with_error(e),
+        });
 
         let timed = pargs
             .opt_value_from_str(["-t", "--timed"])
             .unwrap()
-            .map(|x| TargetStringType::Timed(x));
+            .map(TargetStringType::Timed);
         let words = pargs
             .opt_value_from_str(["-w", "--words"])
             .unwrap()
-            .map(|x| TargetStringType::Words(x));
+            .map(TargetStringType::Words);
+        let quote = pargs
+            .contains(["-q", "--quote"])
+            .then_some(TargetStringType::Quote);
+        let code = pargs
+            .opt_value_from_str::<_, PathBuf>(["-c", "--code"])
+            .unwrap_or_else(|e| exit_with_error(e))
+            .map(|path| match Code::load(&path) {
+                Ok(code) => code,
+                Err(e) => exit_with_error(format!("{}: {}", path.display(), e)),
+            });
 
-        Args {
-            target_type: timed.or(words).unwrap_or(dargs.target_type),
+        let dict = pargs
+            .opt_value_from_fn(["-d", "--dict"], dict::builtin)
+            .unwrap_or_else(|e| exit_with_error(e));
+        let wordlist = pargs
+            .opt_value_from_str::<_, PathBuf>("--wordlist")
+            .unwrap_or_else(|e| exit_with_error(e))
+            .map(|path| match WordList::load(&path) {
+                Ok(list) => Rc::new(list) as Rc<dyn WordSource>,
+                Err(e) => exit_with_error(format!("{}: {}", path.display(), e)),
+            });
+        let sampling = pargs
+            .opt_value_from_str("--sampling")
+            .unwrap_or_else(|e| exit_with_error(e));
+        let toggle = |pargs: &mut pico_args::Arguments, on: &'static str, off: &'static str| match (
+            pargs.contains(on),
+            pargs.contains(off),
+        ) {
+            (true, true) => exit_with_error(format!("{} and {} contradict", on, off)),
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        };
+        let punctuation = toggle(&mut pargs, "--punctuation", "--no-punctuation");
+        let numbers = toggle(&mut pargs, "--numbers", "--no-numbers");
+        let quotes = pargs
+            .opt_value_from_str::<_, PathBuf>("--quotes")
+            .unwrap_or_else(|e| exit_with_error(e))
+            .map(|path| match quotes::load(&path) {
+                Ok(quotes) => quotes,
+                Err(e) => exit_with_error(format!("{}: {}", path.display(), e)),
+            });
+
+        let args = Args {
+            target_type: challenge
+                .as_ref()
+                .map(|c| c.mode)
+                .or(timed)
+                .or(words)
+                .or(quote)
+                .or(code.as_ref().map(|_| TargetStringType::Code))
+                .unwrap_or(dargs.target_type),
+            dict: challenge_dict
+                .or(wordlist)
+                .or(dict)
+                .unwrap_or_else(|| Rc::clone(&dargs.dict)),
+            custom_quotes: quotes.is_some(),
+            quotes: quotes.unwrap_or(dargs.quotes),
+            code,
+            sampling: challenge
+                .as_ref()
+                .map(|c| c.sampling)
+                .or(sampling)
+                .unwrap_or(dargs.sampling),
+            modifiers: challenge.as_ref().map_or(
+                Modifiers {
+                    punctuation: punctuation.or(dargs.modifiers.punctuation),
+                    numbers: numbers.or(dargs.modifiers.numbers),
+                },
+                |c| c.modifiers,
+            ),
+            correction: pargs
+                .opt_value_from_str("--correction")
+                .unwrap_or_else(|e| exit_with_error(e))
+                .unwrap_or(dargs.correction),
+            seed: challenge.map(|c| c.seed).or_else(|| {
+                pargs
+                    .opt_value_from_str(["-s", "--seed"])
+                    .unwrap_or_else(|e| exit_with_error(e))
+            }),
+            history: if pargs.contains("--no-history") {
+                None
+            } else {
+                dargs.history
+            },
+            ghost: pargs.contains("--ghost") || dargs.ghost,
             chart_height: pargs
                 .opt_value_from_str(["-H", "--chart-height"])
                 .unwrap()
@@ -95,46 +391,40 @@ impl Args {
                 .opt_value_from_str(["-M", "--chart-max-wpm"])
                 .unwrap()
                 .unwrap_or(dargs.chart_max_wpm),
-            ..dargs
+            theme: dargs.theme,
+            keys: dargs.keys,
+        };
+        if args.target_type == TargetStringType::Quote {
+            modifiers::check_quotes(&args.quotes, args.modifiers)
+                .unwrap_or_else(|e| exit_with_error(e));
         }
+        args
     }
 }
 
+/// Report a bad command line and exit
+fn exit_with_error(err: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", err);
+    std::process::exit(2);
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse_env();
 
-    enable_raw_mode()?;
-
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-
-    let backend = CrosstermBackend::new(stdout);
+    let mut app = App::new(args, Rc::new(SystemClock));
 
-    let mut terminal = Terminal::new(backend)?;
+    let mut terminal = setup_terminal()?;
 
     let rx = input_handling_thread(&terminal);
 
-    let mut app = App::new(args);
-
-    terminal.clear()?;
-
     loop {
-        terminal.draw(|f| app.draw(f))?;
+        terminal.draw(|f| app.draw(f, f.size()))?;
 
         match rx.recv()? {
             Event::Tick => app.on_tick()?,
-            Event::Input(key) => match key.code {
-                KeyCode::Char('q') => {
-                    disable_raw_mode()?;
-                    execute!(
-                        terminal.backend_mut(),
-                        LeaveAlternateScreen,
-                        DisableMouseCapture
-                    )?;
-                    terminal.show_cursor()?;
-                    break;
-                }
-                code => app.on_key(code)?,
+            Event::Input(key) => match app.command(key) {
+                Some(Command::Quit) => break,
+                _ => app.on_key(key)?,
             },
         };
     }
@@ -142,6 +432,58 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// The terminal in raw mode on the alternate screen, which is handed back when this is
+/// dropped, whether by returning, an error or a panic
+struct TerminalGuard(Terminal<Backend>);
+
+impl std::ops::Deref for TerminalGuard {
+    type Target = Terminal<Backend>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Take over the terminal with a blank alternate screen in raw mode
+fn setup_terminal() -> Result<TerminalGuard, Box<dyn std::error::Error>> {
+    // Restore before the panic message is printed, or it is lost with the alternate screen
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        hook(info);
+    }));
+
+    enable_raw_mode()?;
+
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+
+    let backend = CrosstermBackend::new(stdout);
+
+    let mut terminal = TerminalGuard(Terminal::new(backend)?);
+    terminal.clear()?;
+    Ok(terminal)
+}
+
+/// Hand the terminal back the way `setup_terminal` found it. This is done on the way out,
+/// so failures are ignored
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+}
+
 fn input_handling_thread(_terminal: &Terminal<Backend>) -> Receiver<Event> {
     let (tx, rx) = mpsc::channel();
 
@@ -172,11 +514,23 @@ fn input_handling_thread(_terminal: &Terminal<Backend>) -> Receiver<Event> {
     rx
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Words generated ahead of the typist in timed tests
+const LOOKAHEAD_WORDS: usize = 100;
+/// Completed words kept in the window before the oldest are dropped, if the text was never drawn
+const WINDOW_WORDS: usize = 100;
+/// Lines of text shown in the test viewport
+const VISIBLE_LINES: usize = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum TargetStringType {
     /// Seconds
     Timed(usize),
     Words(usize),
+    /// A passage from the quote collection
+    Quote,
+    /// The source file given with `--code`
+    Code,
 }
 
 impl Default for TargetStringType {
@@ -185,8 +539,48 @@ impl Default for TargetStringType {
         TargetStringType::Timed(15)
     }
 }
+
+impl std::fmt::Display for TargetStringType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetStringType::Timed(n) => write!(f, "time {}", n),
+            TargetStringType::Words(n) => write!(f, "words {}", n),
+            TargetStringType::Quote => write!(f, "quote"),
+            TargetStringType::Code => write!(f, "code"),
+        }
+    }
+}
+/// Which finished words backspace can go back into
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Correction {
+    Never,
+    /// Only words with a mistake, like monkeytype
+    #[default]
+    Incorrect,
+    Always,
+}
+
+impl std::str::FromStr for Correction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(Correction::Never),
+            "incorrect" => Ok(Correction::Incorrect),
+            "always" => Ok(Correction::Always),
+            _ => Err(format!(
+                "unknown correction policy '{}', expected never, incorrect or always",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum TestState {
+    /// Picking the next test
+    Menu,
     Pre,
     Running,
     Post,
@@ -206,13 +600,66 @@ struct App {
     wpm: f64,
     correct: usize,
     accuracy: f64,
+    /// Every character typed, divided by 5, per minute
+    raw_wpm: f64,
+    /// Correctly typed characters, divided by 5, per minute
+    net_wpm: f64,
+    /// Percentage of keystrokes that were correct, including ones later corrected
+    char_accuracy: f64,
+    /// How steady the typing speed was from second to second, see `consistency`
+    consistency: f64,
+    /// Characters typed, including ones later deleted
+    keystrokes: usize,
+    correct_keystrokes: usize,
+    /// Characters typed during each second of the test
+    keys_per_second: Vec<usize>,
+    /// Every key pressed since the test started
+    keystrokes_log: Vec<Keystroke>,
     accuracy_history: Vec<(f64, f64)>,
     wpm_history: Vec<(f64, f64)>,
     progress: f64,
+    /// Completed words that have been dropped from the front of the window
+    words_dropped: usize,
+    /// How many of the dropped words were typed correctly
+    correct_dropped: usize,
+    /// Characters, including separators, of the correctly typed dropped words
+    correct_chars_dropped: usize,
+    /// Index into `args.quotes` of the passage being typed in a quote test
+    quote: Option<usize>,
+    /// What the words of this test were generated from
+    seed: u64,
+    rng: ChaCha8Rng,
+    punctuator: Punctuator,
+    /// Which characters were mistyped during the test, and as what
+    key_errors: KeyErrors,
+    /// Why the last test could not be saved to the history
+    history_error: Option<String>,
+    /// Why the test picked in the menu could not start
+    menu_error: Option<String>,
+    /// Width of the text viewport the last time it was drawn, used to scroll by whole lines
+    text_width: Cell<u16>,
+    /// The best run of every mode, when racing against a ghost
+    ghosts: Vec<Rc<Ghost>>,
+    /// The run raced against in this test
+    ghost: Option<Rc<Ghost>>,
+    /// Choices in the menu, while it is open
+    menu: Menu,
+    clock: Rc<dyn Clock>,
     args: Args,
 }
 impl App {
-    fn new(args: Args) -> App {
+    fn new(args: Args, clock: Rc<dyn Clock>) -> App {
+        let now = clock.now();
+        let ghosts = match &args.history {
+            Some(path) if args.ghost => {
+                let records = history::load(path).unwrap_or_default();
+                Ghost::bests(&records, &args.keys)
+                    .into_iter()
+                    .map(Rc::new)
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
         let mut app = App {
             target_type: args.target_type,
             target_str: String::new(),
@@ -220,14 +667,43 @@ impl App {
             target_words: Vec::new(),
             enterd_words: Vec::new(),
             running: TestState::Pre,
-            start: Instant::now(),
-            prev_hist: Instant::now(),
-            now: Instant::now(),
+            start: now,
+            prev_hist: now,
+            now,
             wpm: 0.,
             accuracy: 0.,
+            raw_wpm: 0.,
+            net_wpm: 0.,
+            char_accuracy: 0.,
+            consistency: 0.,
+            keystrokes: 0,
+            correct_keystrokes: 0,
+            keys_per_second: Vec::new(),
+            keystrokes_log: Vec::new(),
             accuracy_history: Vec::with_capacity(100),
             wpm_history: Vec::with_capacity(100),
             progress: 0.,
+            words_dropped: 0,
+            correct_dropped: 0,
+            correct_chars_dropped: 0,
+            quote: None,
+            seed: 0,
+            rng: ChaCha8Rng::seed_from_u64(0),
+            punctuator: Punctuator::default(),
+            key_errors: KeyErrors::default(),
+            history_error: None,
+            menu_error: None,
+            text_width: Cell::new(0),
+            ghosts,
+            ghost: None,
+            menu: Menu::new(
+                args.target_type,
+                args.code.is_some(),
+                &args.dict,
+                args.sampling,
+                args.modifiers,
+            ),
+            clock,
             args,
             correct: 0,
         };
@@ -236,27 +712,203 @@ impl App {
     }
 
     fn new_target_string(&mut self, ty: TargetStringType) {
-        let words = match ty {
-            // Generate enough words for 300wpm for the time
-            // TODO: handle Timed by continously generating new words
-            TargetStringType::Timed(n) => n * 300 / 60,
-            TargetStringType::Words(n) => n,
+        self.clear_target();
+        let kind = self.kind();
+        self.ghost = self.ghosts.iter().find(|g| *g.kind() == kind).cloned();
+        // Random seeds are kept short so they are easy to pass on
+        self.seed = self
+            .args
+            .seed
+            .unwrap_or_else(|| thread_rng().gen::<u32>().into());
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let (punctuation, numbers) = self.args.modifiers.of(ty);
+        self.punctuator = Punctuator::new(punctuation, numbers);
+        match ty {
+            // Timed tests keep generating words as the typist approaches the end
+            TargetStringType::Timed(_) => self.extend_target(LOOKAHEAD_WORDS),
+            TargetStringType::Words(n) => self.extend_target(n),
+            TargetStringType::Quote | TargetStringType::Code => {
+                if ty == TargetStringType::Quote {
+                    // There is always one, see `modifiers::check_quotes`
+                    let typeable =
+                        modifiers::typeable_quotes(&self.args.quotes, self.args.modifiers);
+                    self.quote = Some(typeable[self.rng.gen_range(0..typeable.len())]);
+                }
+                for (word, sep) in self.fixed_words(ty) {
+                    self.push_word(&word, sep);
+                }
+            }
+        }
+        self.skip_indentation();
+    }
+
+    /// The words of the quote or code being typed, each with the separator after it
+    fn fixed_words(&self, ty: TargetStringType) -> Vec<(String, char)> {
+        match (ty, self.quote) {
+            (TargetStringType::Quote, Some(i)) => {
+                let text = &self.args.quotes[i].text;
+                let (punctuation, numbers) = self.args.modifiers.of(ty);
+                modifiers::quote_words(text, punctuation, numbers)
+                    .into_iter()
+                    .map(|word| (word, ' '))
+                    .collect()
+            }
+            (TargetStringType::Code, _) => self.args.code.iter().flat_map(Code::words).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Text of the words dropped from the front of the window. Only the window is kept,
+    /// so the rest is generated again the way `new_target_string` and `advance_window` did
+    fn dropped_target(&self) -> String {
+        let words = match self.target_type {
+            TargetStringType::Timed(_) | TargetStringType::Words(_) => {
+                let chunk = match self.target_type {
+                    TargetStringType::Words(n) => n,
+                    _ => LOOKAHEAD_WORDS,
+                };
+                let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+                let (punctuation, numbers) = self.args.modifiers.of(self.target_type);
+                let mut punctuator = Punctuator::new(punctuation, numbers);
+                let mut words = Vec::new();
+                while words.len() < self.words_dropped {
+                    let dict = &*self.args.dict;
+                    let drawn =
+                        draw_words(dict, self.args.sampling, &mut rng, &mut punctuator, chunk);
+                    words.extend(drawn.into_iter().map(|word| (word, ' ')));
+                }
+                words
+            }
+            ty => self.fixed_words(ty),
         };
-        let mut rng = thread_rng();
-        let dict = dict::ENGLISH;
-        let choose = Uniform::from(0..dict.len());
-        self.target_str = (0..words).map(|_| dict[choose.sample(&mut rng)]).join(" ");
-
-        self.enterd_str = String::with_capacity(self.target_str.len());
-        self.target_words = self
-            .target_str
-            .char_indices()
-            .filter(|&(_, c)| c == ' ')
-            .map(|(i, _)| i + 1)
-            .collect();
-        // self.target_words.push(self.target_words.len());
-        self.enterd_words = Vec::with_capacity(self.target_words.len());
+        words
+            .into_iter()
+            .take(self.words_dropped)
+            .map(|(word, sep)| format!("{}{}", word, sep))
+            .collect()
+    }
+
+    /// Start over with the text of a recorded test as the target
+    fn replay_target(&mut self, text: &str) {
+        self.running = TestState::Pre;
+        self.clear_target();
+        // Words end at a separator, except that indentation belongs to the word after it
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            if is_separator(c) && !text[start..i].trim_start_matches(' ').is_empty() {
+                self.push_word(&text[start..i], c);
+                start = i + c.len_utf8();
+            }
+        }
+        self.skip_indentation();
+    }
+
+    fn clear_target(&mut self) {
+        self.target_str.clear();
+        self.target_words.clear();
+        self.enterd_str.clear();
+        self.enterd_words.clear();
         self.enterd_words.push(0);
+        self.words_dropped = 0;
+        self.correct_dropped = 0;
+        self.correct_chars_dropped = 0;
+        self.quote = None;
+    }
+
+    /// Append `words` more words from the dictionary to the target
+    fn extend_target(&mut self, words: usize) {
+        let dict = Rc::clone(&self.args.dict);
+        let sampling = self.args.sampling;
+        for word in draw_words(&*dict, sampling, &mut self.rng, &mut self.punctuator, words) {
+            self.push_word(&word, ' ');
+        }
+    }
+
+    /// Append a single word, followed by its separator, to the target
+    fn push_word(&mut self, word: &str, sep: char) {
+        self.target_str.push_str(word);
+        self.target_str.push(sep);
+        self.target_words.push(self.target_str.len());
+    }
+
+    /// Keep the target ahead of the typist and drop completed words from the front,
+    /// so an arbitrarily long test only ever holds a bounded window of text
+    fn advance_window(&mut self) {
+        if self.target_is_infinite()
+            && self.target_words.len() - self.enterd_words.len() < LOOKAHEAD_WORDS
+        {
+            self.extend_target(LOOKAHEAD_WORDS);
+        }
+        // Drop the lines that have scrolled out of view above the text viewport
+        let width = self.text_width.get();
+        if width > 0 {
+            let starts = self.get_line_starts(width.into());
+            let current = current_line(&starts, self.enterd_words.len() - 1);
+            if current >= 2 {
+                self.drop_words(starts[current - 1]);
+            }
+        } else if self.enterd_words.len() > 2 * WINDOW_WORDS {
+            // Nothing has been drawn yet, fall back to keeping a fixed number of words.
+            // The last entered word is the one we are typing, so it is never dropped
+            self.drop_words(WINDOW_WORDS);
+        }
+    }
+
+    /// Remove the first `n` (completed) words from the window, remembering their results
+    fn drop_words(&mut self, n: usize) {
+        let (correct, chars) = izip!(self.get_target_words(), self.get_enterd_words())
+            .take(n)
+            .filter(|(t, e)| t == e)
+            .fold((0, 0), |(words, chars), (t, _)| {
+                (words + 1, chars + t.graphemes(true).count())
+            });
+        self.correct_dropped += correct;
+        self.correct_chars_dropped += chars;
+        self.words_dropped += n;
+
+        let (tlen, elen) = (self.target_words[n - 1], self.enterd_words[n - 1]);
+        self.target_str.drain(..tlen);
+        self.enterd_str.drain(..elen);
+        self.target_words.drain(..n);
+        self.enterd_words.drain(..n);
+        self.target_words.iter_mut().for_each(|w| *w -= tlen);
+        self.enterd_words.iter_mut().for_each(|w| *w -= elen);
+    }
+
+    /// The code that starts this test again, unless it was typed from local files
+    fn challenge(&self) -> Option<Challenge> {
+        let dict = self.args.dict.builtin()?;
+        if self.target_type == TargetStringType::Code
+            || (self.target_type == TargetStringType::Quote && self.args.custom_quotes)
+        {
+            return None;
+        }
+        Some(Challenge {
+            mode: self.target_type,
+            dict: dict.name().to_owned(),
+            sampling: self.args.sampling,
+            modifiers: self.args.modifiers,
+            seed: self.seed,
+        })
+    }
+
+    /// What the current test is, to race it against a ghost of the same
+    fn kind(&self) -> TestKind {
+        let dict = self.target_uses_dict().then(|| self.args.dict.name());
+        TestKind::new(
+            self.target_type,
+            dict,
+            self.args.sampling,
+            self.args.modifiers,
+        )
+    }
+
+    /// Whether the words come from `args.dict`, rather than a quote or code
+    fn target_uses_dict(&self) -> bool {
+        matches!(
+            self.target_type,
+            TargetStringType::Timed(_) | TargetStringType::Words(_)
+        )
     }
 
     fn target_is_infinite(&self) -> bool {
@@ -265,31 +917,8 @@ impl App {
 
     fn on_tick(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if self.running == TestState::Running {
-            self.now = Instant::now();
-
-            let tws = self.get_target_words().collect_vec();
-            let ews = self.get_enterd_words().collect_vec();
-            let ews = &ews[..ews.len() - 1]; // The last word is the one we are typing, so leave it out
-            let (correct, total) = izip!(tws, ews).fold((0, 0), |(corr, tot), (t, &e)| {
-                (corr + if t == e { 1 } else { 0 }, tot + 1)
-            });
-            self.correct = correct;
-            let (correct, total) = (correct as f64, total as f64);
-            let tspan = (self.now - self.start).as_secs_f64();
-            self.accuracy = if total == 0. {
-                0.
-            } else {
-                correct * 100. / total
-            };
-            match self.target_type {
-                TargetStringType::Timed(tot) => {
-                    self.progress = tspan * 100. / (tot as f64);
-                }
-                TargetStringType::Words(_) => {
-                    self.progress = total * 100. / self.target_words.len() as f64;
-                }
-            }
-            self.wpm = correct / tspan * 60.;
+            self.now = self.clock.now();
+            self.update_stats();
 
             if (self.now - self.prev_hist).as_millis() > 100 {
                 if self.accuracy > 0.0 {
@@ -297,47 +926,313 @@ impl App {
                 }
                 self.wpm_history.push((self.progress, self.wpm));
 
-                self.prev_hist = Instant::now();
+                self.prev_hist = self.now;
             }
 
             if self.progress >= 100. {
-                self.end_test()
+                self.end_test();
+                self.save_result();
             }
         }
         Ok(())
     }
 
+    fn update_stats(&mut self) {
+        let tws = self.get_target_words().collect_vec();
+        let ews = self.get_enterd_words().collect_vec();
+        // The last word is the one we are typing, so leave it out unless it was finished
+        let done = match ews.last() {
+            Some(last) if last.ends_with(is_separator) => ews.len(),
+            _ => ews.len() - 1,
+        };
+        let (correct, total) = izip!(&tws, &ews[..done]).fold(
+            (self.correct_dropped, self.words_dropped),
+            |(corr, tot), (t, e)| (corr + if t == e { 1 } else { 0 }, tot + 1),
+        );
+        // Whole correct words, and the correct start of the word being typed
+        let correct_chars = izip!(&tws, &ews[..done])
+            .filter(|(t, e)| t == e)
+            .map(|(t, _)| t.graphemes(true).count())
+            .chain(
+                izip!(&tws[done..], &ews[done..])
+                    .map(|(t, e)| merge_word(t, e).0.graphemes(true).count()),
+            )
+            .sum::<usize>()
+            + self.correct_chars_dropped;
+        self.correct = correct;
+        let (correct, total) = (correct as f64, total as f64);
+        let tspan = (self.now - self.start).as_secs_f64();
+        self.accuracy = if total == 0. {
+            0.
+        } else {
+            correct * 100. / total
+        };
+        match self.target_type {
+            TargetStringType::Timed(tot) => {
+                self.progress = tspan * 100. / (tot as f64);
+            }
+            TargetStringType::Words(_) | TargetStringType::Quote | TargetStringType::Code => {
+                let words = self.words_dropped + self.target_words.len();
+                self.progress = total * 100. / words as f64;
+            }
+        }
+        let per_minute = |n: f64| if tspan > 0. { n / tspan * 60. } else { 0. };
+        self.wpm = per_minute(correct);
+        self.raw_wpm = per_minute(self.keystrokes as f64 / 5.);
+        self.net_wpm = per_minute(correct_chars as f64 / 5.);
+        self.char_accuracy = if self.keystrokes == 0 {
+            0.
+        } else {
+            self.correct_keystrokes as f64 * 100. / self.keystrokes as f64
+        };
+        // Only whole seconds, the current one is still being typed
+        let seconds = (tspan as usize).min(self.keys_per_second.len());
+        self.consistency = consistency(&self.keys_per_second[..seconds]);
+    }
+
     fn start_test(&mut self) {
         self.running = TestState::Running;
-        self.start = Instant::now();
+        self.start = self.clock.now();
         self.now = self.start;
         self.prev_hist = self.start;
         self.accuracy_history.clear();
         self.wpm_history.clear();
+        self.key_errors.clear();
+        self.keystrokes = 0;
+        self.correct_keystrokes = 0;
+        self.keys_per_second.clear();
+        self.keystrokes_log.clear();
     }
     fn end_test(&mut self) {
+        if self.running == TestState::Running {
+            self.now = self.clock.now();
+            self.update_stats();
+        }
         self.running = TestState::Post;
     }
+
+    /// Append the results of the test that just finished to the history file,
+    /// and race against it from the next test on if it is the new best
+    fn save_result(&mut self) {
+        let record = Record {
+            version: history::VERSION,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |t| t.as_secs()),
+            mode: self.target_type,
+            dict: if self.target_uses_dict() {
+                Some(self.args.dict.name().to_owned())
+            } else {
+                None
+            },
+            source: match self.target_type {
+                TargetStringType::Quote => self.quote.map(|i| self.args.quotes[i].source.clone()),
+                TargetStringType::Code => self.args.code.as_ref().map(|c| c.name.clone()),
+                TargetStringType::Timed(_) | TargetStringType::Words(_) => None,
+            },
+            seed: (self.target_type != TargetStringType::Code).then_some(self.seed),
+            sampling: self.args.sampling,
+            modifiers: self.args.modifiers,
+            correction: self.args.correction,
+            wpm: self.wpm,
+            accuracy: self.accuracy,
+            raw_wpm: self.raw_wpm,
+            net_wpm: self.net_wpm,
+            char_accuracy: self.char_accuracy,
+            consistency: self.consistency,
+            correct: self.correct,
+            duration: (self.now - self.start).as_secs_f64(),
+            completed: self.progress >= 100.,
+            wpm_history: self.wpm_history.clone(),
+            accuracy_history: self.accuracy_history.clone(),
+            keystrokes: std::mem::take(&mut self.keystrokes_log),
+            target: self.dropped_target() + &self.target_str,
+        };
+        if let Some(path) = &self.args.history {
+            self.history_error = history::append(path, &record)
+                .err()
+                .map(|e| format!("History not saved: {}", e));
+        }
+        if self.args.ghost && record.completed {
+            let kind = record.kind();
+            let best = self.ghosts.iter().position(|g| *g.kind() == kind);
+            match (best, Ghost::new(&record, &self.args.keys)) {
+                (Some(i), Some(ghost)) if ghost.wpm() > self.ghosts[i].wpm() => {
+                    self.ghosts[i] = Rc::new(ghost)
+                }
+                (None, Some(ghost)) => self.ghosts.push(Rc::new(ghost)),
+                _ => {}
+            }
+        }
+    }
     fn new_test(&mut self) {
         self.running = TestState::Pre;
-        self.now = Instant::now();
+        self.now = self.clock.now();
         self.new_target_string(self.target_type)
     }
 
-    fn on_key(&mut self, key: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
-        let ok = match key {
-            KeyCode::Char(' ') | KeyCode::Right | KeyCode::Enter => {
+    /// Finish the word being typed with `sep` and move on to the next one,
+    /// skipping over the indentation the next word starts with
+    fn complete_word(&mut self, sep: char) {
+        self.enterd_str.push(sep);
+        *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
+        if self.enterd_words.len() == self.target_words.len() {
+            self.end_test()
+        } else {
+            self.enterd_words.push(*self.enterd_words.last().unwrap());
+            self.advance_window();
+            self.skip_indentation();
+        }
+    }
+
+    /// Enter the indentation of the word about to be typed, so code is typed without it
+    fn skip_indentation(&mut self) {
+        let next = self.get_target_words().nth(self.enterd_words.len() - 1);
+        let indent = next.map_or(0, |w| w.len() - w.trim_start_matches(' ').len());
+        self.enterd_str.push_str(&" ".repeat(indent));
+        *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
+    }
+
+    /// The character of the target that the next key press should produce
+    fn expected_char(&self) -> Option<char> {
+        let i = self.enterd_words.len() - 1;
+        let typed = self.get_enterd_words().nth(i)?;
+        let target = self.get_target_words().nth(i)?;
+        match target.strip_prefix(typed) {
+            // Possibly the middle of a character made of several, like an accent after a letter
+            Some(rest) => rest.chars().next(),
+            None => {
+                let typed = typed.graphemes(true).count();
+                target.graphemes(true).nth(typed)?.chars().next()
+            }
+        }
+    }
+
+    /// Keep track of which characters were mistyped, and when characters were typed
+    fn record_key(&mut self, typed: char) {
+        let expected = self.expected_char();
+        if let Some(expected) = expected {
+            self.key_errors.record(expected, typed);
+        }
+        self.keystrokes += 1;
+        if expected == Some(typed) {
+            self.correct_keystrokes += 1;
+        }
+        let second = (self.clock.now() - self.start).as_secs() as usize;
+        if self.keys_per_second.len() <= second {
+            self.keys_per_second.resize(second + 1, 0);
+        }
+        self.keys_per_second[second] += 1;
+    }
+
+    /// Whether anything beyond the skipped indentation has been typed for the current word
+    fn typing_word(&self) -> bool {
+        self.get_enterd_words()
+            .last()
+            .is_some_and(|w| !w.trim_start_matches(' ').is_empty())
+    }
+
+    /// The separator the final word of a finite test ends with, once that word has been
+    /// typed correctly. This ends the test without waiting for a space
+    fn typed_last_word(&self) -> Option<char> {
+        if self.target_is_infinite() || self.enterd_words.len() != self.target_words.len() {
+            return None;
+        }
+        let target = self.get_target_words().last()?;
+        let word = target.trim_end_matches(is_separator);
+        if self.get_enterd_words().last() == Some(word) {
+            target[word.len()..].chars().next()
+        } else {
+            None
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
+        let was = self.running;
+        let before = (self.enterd_str.len(), self.enterd_words.len());
+        let pressed = self.clock.now();
+
+        if self.running == TestState::Menu {
+            self.on_menu_key(key.code);
+            return Ok(());
+        }
+
+        match self.command(key) {
+            Some(command) => self.run_command(command),
+            None => self.type_key(key),
+        }
+
+        // Log everything from the key that started the test until it finished
+        let started = was == TestState::Pre
+            && self.running != TestState::Pre
+            && matches!(key.code, KeyCode::Char(_));
+        if was == TestState::Running || started {
+            self.keystrokes_log.push(Keystroke {
+                time: pressed.saturating_duration_since(self.start).as_micros() as u64,
+                key,
+                accepted: before != (self.enterd_str.len(), self.enterd_words.len()),
+            });
+            // The test finished because of this key
+            if self.running == TestState::Post {
+                self.save_result();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The command a key runs. Plain characters always type during a test, and before
+    /// it, since the first of them starts it
+    fn command(&self, key: KeyEvent) -> Option<Command> {
+        let typing = matches!(self.running, TestState::Pre | TestState::Running);
+        self.args.keys.command(key, typing)
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Restart => {
                 if self.running == TestState::Running {
-                    if self.enterd_str.chars().last() != Some(' ') {
-                        self.enterd_str.push(' ');
-                        // *self.enterd_words.last_mut().unwrap() += 1;
-                        *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
-                        if self.enterd_words.len() == self.target_words.len() {
-                            self.end_test()
-                        } else {
-                            self.enterd_words.push(*self.enterd_words.last().unwrap());
-                        }
-                    }
+                    self.new_test()
+                } else {
+                    self.open_menu()
+                }
+            }
+            Command::End => {
+                if self.running != TestState::Post {
+                    self.end_test()
+                } else {
+                    self.new_test()
+                }
+            }
+            Command::DeleteWord => {
+                if self.running == TestState::Running {
+                    self.delete_word()
+                }
+            }
+            // The terminal belongs to `main`, which quits
+            Command::Quit => {}
+        }
+    }
+
+    /// A key that isn't bound to a command, which mostly types the test
+    fn type_key(&mut self, key: KeyEvent) {
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return;
+        }
+        match key.code {
+            KeyCode::Enter if self.target_type == TargetStringType::Code => {
+                if self.running == TestState::Running && self.typing_word() {
+                    self.record_key('\n');
+                    self.complete_word('\n');
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Right | KeyCode::Enter => {
+                if self.running == TestState::Running && self.typing_word() {
+                    self.record_key(' ');
+                    self.complete_word(' ');
                 }
                 // else if self.running == TestState::Post {
                 //     self.new_test();
@@ -345,28 +1240,20 @@ impl App {
             }
             KeyCode::Char(c) => {
                 if self.running != TestState::Post {
-                    self.enterd_str.push(c);
-                    *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
                     if self.running == TestState::Pre {
                         self.start_test()
                     }
+                    self.record_key(c);
+                    self.enterd_str.push(c);
+                    *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
+                    if let Some(sep) = self.typed_last_word() {
+                        self.complete_word(sep);
+                    }
                 }
             }
-            KeyCode::Backspace => match self.enterd_str.pop() {
-                Some(' ') => {
-                    self.enterd_str.push(' ');
-                }
-                Some(_) => {
-                    *self.enterd_words.last_mut().unwrap() -= 1;
-                }
-                None => {}
-            },
-            KeyCode::Esc => self.new_test(),
-            KeyCode::Tab => {
-                if self.running != TestState::Post {
-                    self.end_test()
-                } else {
-                    self.new_test()
+            KeyCode::Backspace => {
+                if self.running == TestState::Running {
+                    self.backspace()
                 }
             }
             // TODO: any more functions needed?
@@ -382,24 +1269,123 @@ impl App {
             KeyCode::Insert => {}
             KeyCode::F(_) => {}
             KeyCode::Null => {}
+            KeyCode::Esc => {}
+            KeyCode::Tab => {}
         };
+    }
+
+    /// Start of the word being typed in `enterd_str`, after any skipped indentation
+    fn typed_word_start(&self) -> usize {
+        let i = self.enterd_words.len() - 1;
+        let start = if i == 0 { 0 } else { self.enterd_words[i - 1] };
+        start + self.enterd_str[start..].len()
+            - self.enterd_str[start..].trim_start_matches(' ').len()
+    }
 
-        Ok(ok)
+    /// Whether the correction policy allows going back into the previous word
+    fn can_go_back(&self) -> bool {
+        let i = self.enterd_words.len() - 1;
+        if i == 0 {
+            return false;
+        }
+        match self.args.correction {
+            Correction::Never => false,
+            Correction::Incorrect => {
+                self.get_target_words().nth(i - 1) != self.get_enterd_words().nth(i - 1)
+            }
+            Correction::Always => true,
+        }
     }
 
-    fn draw(&self, f: &mut Frame<Backend>) {
+    /// Make the previous word the one being typed again, without its separator
+    fn go_back(&mut self) {
+        self.enterd_words.pop();
+        let end = *self.enterd_words.last().unwrap();
+        self.enterd_str.truncate(end);
+        self.enterd_str.pop();
+        *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
+    }
+
+    fn backspace(&mut self) {
+        let start = self.typed_word_start();
+        if self.enterd_str.len() > start {
+            // A whole character as shown, with any accents typed after it
+            let last = self.enterd_str[start..].grapheme_indices(true).next_back();
+            self.enterd_str.truncate(start + last.map_or(0, |(i, _)| i));
+            *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
+        } else if self.can_go_back() {
+            self.go_back();
+        }
+    }
+
+    fn delete_word(&mut self) {
+        if self.enterd_str.len() == self.typed_word_start() {
+            if !self.can_go_back() {
+                return;
+            }
+            self.go_back();
+        }
+        self.enterd_str.truncate(self.typed_word_start());
+        *self.enterd_words.last_mut().unwrap() = self.enterd_str.len();
+    }
+
+    fn open_menu(&mut self) {
+        self.menu = Menu::new(
+            self.target_type,
+            self.args.code.is_some(),
+            &self.args.dict,
+            self.args.sampling,
+            self.args.modifiers,
+        );
+        self.menu_error = None;
+        self.running = TestState::Menu;
+    }
+
+    fn on_menu_key(&mut self, key: KeyCode) {
+        match self.menu.on_key(key) {
+            MenuAction::Stay => {}
+            MenuAction::Start => {
+                let (target_type, dict, sampling, modifiers) = self.menu.choice();
+                if target_type == TargetStringType::Quote {
+                    if let Err(e) = modifiers::check_quotes(&self.args.quotes, modifiers) {
+                        self.menu_error = Some(format!("Quote test not started: {}", e));
+                        return;
+                    }
+                }
+                self.target_type = target_type;
+                self.args.dict = dict;
+                self.args.sampling = sampling;
+                self.args.modifiers = modifiers;
+                self.new_test();
+            }
+            MenuAction::Cancel => self.new_test(),
+        }
+    }
+
+    fn draw(&self, f: &mut Frame<Backend>, size: Rect) {
+        let mut constraints = vec![
+            Constraint::Length(2 + 1),
+            Constraint::Length((2 + self.args.chart_height).try_into().unwrap()),
+            Constraint::Min(2 + 3),
+        ];
+        if self.running == TestState::Post {
+            constraints.push(Constraint::Length(2 + 5));
+        }
         let chunks = Layout::default()
             .direction(tui::layout::Direction::Vertical)
-            .constraints([
-                Constraint::Length(2 + 1),
-                Constraint::Length((2 + self.args.chart_height).try_into().unwrap()),
-                Constraint::Min(2 + 3),
-            ])
-            .split(f.size());
+            .constraints(constraints)
+            .split(size);
 
         self.title_widget(f, chunks[0]);
         self.stats_widget(f, chunks[1]);
-        self.text_widget(f, chunks[2]);
+        if self.running == TestState::Menu {
+            self.menu_widget(f, chunks[2]);
+        } else {
+            self.text_widget(f, chunks[2]);
+        }
+        if self.running == TestState::Post {
+            self.heatmap_widget(f, chunks[3]);
+        }
     }
 
     fn get_target_words(&self) -> impl Iterator<Item = &str> {
@@ -416,24 +1402,53 @@ impl App {
     }
 
     fn title_widget(&self, f: &mut Frame<Backend>, size: Rect) {
-        let par = Paragraph::new(vec![Spans::from(vec![match self.target_type {
-            TargetStringType::Timed(n) => Span::raw(format!("Time Limit: {} ", n)),
-            TargetStringType::Words(n) => Span::raw(format!("Words: {} ", n)),
-        }])]);
+        let par = Paragraph::new(vec![Spans::from(vec![
+            match self.target_type {
+                TargetStringType::Timed(n) => Span::raw(format!("Time Limit: {} ", n)),
+                TargetStringType::Words(n) => Span::raw(format!("Words: {} ", n)),
+                TargetStringType::Quote => Span::raw("Quote "),
+                TargetStringType::Code => Span::raw(format!(
+                    "Code: {} ",
+                    self.args.code.as_ref().map_or("", |c| &c.name)
+                )),
+            },
+            match (self.quote, self.running) {
+                (Some(i), TestState::Post) => {
+                    Span::raw(format!("- {} ", self.args.quotes[i].source))
+                }
+                (Some(_), _) => Span::raw(""),
+                (None, _) if !self.target_uses_dict() => Span::raw(""),
+                (None, _) => {
+                    let mut text = format!("Dict: {} ", self.args.dict.name());
+                    if self.args.sampling != Sampling::Uniform {
+                        text += &format!("Sampling: {} ", self.args.sampling);
+                    }
+                    Span::raw(text)
+                }
+            },
+            match (self.running, self.challenge()) {
+                (TestState::Post, Some(challenge)) => {
+                    Span::raw(format!("Challenge: {} ", challenge))
+                }
+                (TestState::Post, None) if self.target_type != TargetStringType::Code => {
+                    Span::raw(format!("Seed: {} ", self.seed))
+                }
+                _ => Span::raw(""),
+            },
+        ])]);
         let (msg, fmt) = match self.running {
-            TestState::Pre => (
-                "Ready to Go",
-                Style::default().fg(Color::Black).bg(Color::Yellow),
-            ),
-            TestState::Running => (
-                "Test Running",
-                Style::default().fg(Color::Black).bg(Color::Green),
+            TestState::Menu => (
+                self.menu_error.as_deref().unwrap_or("Choose a Test"),
+                self.args.theme.menu,
             ),
+            TestState::Pre => ("Ready to Go", self.args.theme.ready),
+            TestState::Running => ("Test Running", self.args.theme.running),
             TestState::Post => (
-                "Test Complete",
-                Style::default().fg(Color::White).bg(Color::Red),
+                self.history_error.as_deref().unwrap_or("Test Complete"),
+                self.args.theme.done,
             ),
         };
+        let fmt = Style::default().fg(self.args.theme.highlight_text).bg(fmt);
         let status = Paragraph::new(vec![Spans::from(vec![Span::styled(msg, fmt)])])
             .alignment(Alignment::Right);
         let block = self.block();
@@ -442,92 +1457,132 @@ impl App {
         f.render_widget(status, inner);
     }
 
-    fn text_widget(&self, f: &mut Frame<Backend>, size: Rect) {
+    /// Each word in the window split into (complete, wrong, incomplete) parts
+    fn get_merged_words(&self) -> impl Iterator<Item = (&str, &str, &str)> {
         const STRINGS_CLEARED_BEFORE_FINISH: &str =
             "BUG: Clear the target, user strings when they are complete before drawing";
 
+        self.get_target_words()
+            .zip_longest(self.get_enterd_words())
+            .map(|pair| match pair {
+                itertools::EitherOrBoth::Both(target, enterd) => merge_word(target, enterd),
+                itertools::EitherOrBoth::Left(target) => merge_word(target, ""),
+                itertools::EitherOrBoth::Right(_enterd) => {
+                    unreachable!("{}", STRINGS_CLEARED_BEFORE_FINISH)
+                }
+            })
+    }
+
+    /// Index of the first word of every line when the window is folded to `width` columns
+    fn get_line_starts(&self, width: usize) -> Vec<usize> {
+        let words = self.get_merged_words().zip(self.get_target_words()).map(
+            |((complete, wrong, incomplete), target)| {
+                let len = columns(complete) + columns(wrong) + columns(incomplete);
+                (len, target.ends_with('\n'))
+            },
+        );
+        fold_lines(words, width)
+    }
+
+    fn text_widget(&self, f: &mut Frame<Backend>, size: Rect) {
         let block = self.block().title("Test");
         let inner = block.inner(size);
         let width = inner.width;
+        self.text_width.set(width);
 
+        let theme = &self.args.theme;
         let completed_word_style = Style::default()
-            .bg(Color::Black)
-            .fg(Color::White)
+            .bg(theme.background)
+            .fg(theme.completed)
             .add_modifier(Modifier::UNDERLINED);
-        let completed_part_style = Style::default().bg(Color::Black).fg(Color::Green);
-        let wrong_part_style = Style::default().bg(Color::Black).fg(Color::Red);
-        let incomplete_part_style = Style::default().bg(Color::Black).fg(Color::DarkGray);
+        let completed_part_style = Style::default().bg(theme.background).fg(theme.correct);
+        let wrong_part_style = Style::default().bg(theme.wrong_background).fg(theme.wrong);
+        let incomplete_part_style = Style::default().bg(theme.background).fg(theme.untyped);
         let ongoing_part_style = Style::default()
-            .bg(Color::Black)
-            .fg(Color::Gray)
+            .bg(theme.background)
+            .fg(theme.current)
             .add_modifier(Modifier::BOLD);
+        let ghost_style = Style::default().bg(theme.ghost);
+        let ghost = self.ghost_cursor();
 
-        // TODO: wrapping might be able to be done by this
-        // https://docs.rs/tui/0.15.0/tui/widgets/struct.Wrap.html
-        let (lines, _) = self
-            .get_target_words()
-            .zip_longest(self.get_enterd_words())
-            .map(|pair| match pair {
-                itertools::EitherOrBoth::Both(target, enterd) => merge_word(target, enterd),
-                itertools::EitherOrBoth::Left(target) => merge_word(target, ""),
-                itertools::EitherOrBoth::Right(_enterd) => {
-                    unreachable!(STRINGS_CLEARED_BEFORE_FINISH)
-                }
-            })
+        let words = self
+            .get_merged_words()
             .enumerate()
-            .fold(
-                (vec![vec![]], 0),
-                |(mut lines, linelen), (i, (complete, wrong, incomplete))| {
-                    let spcomplete = Span::styled(
-                        complete,
-                        if wrong.len() == 0 && incomplete.len() <= 1 {
-                            completed_word_style
-                        } else {
-                            completed_part_style
-                        },
-                    );
-                    let spwrong = Span::styled(wrong, wrong_part_style);
-                    let spincomplete = Span::styled(
-                        incomplete,
-                        if i == self.enterd_words.len() - 1 {
-                            ongoing_part_style
-                        } else {
-                            incomplete_part_style
-                        },
-                    );
-
-                    let wordlen = complete.len() + wrong.len() + incomplete.len();
-
-                    let totallen = wordlen + linelen;
-                    let len = if totallen < width.into() || false {
-                        let line = lines.last_mut().unwrap();
-                        if complete.len() > 0 {
-                            line.push(spcomplete)
-                        }
-                        if wrong.len() > 0 {
-                            line.push(spwrong)
-                        }
-                        if incomplete.len() > 0 {
-                            line.push(spincomplete)
-                        }
-                        totallen
+            .map(|(i, (complete, wrong, incomplete))| {
+                let spcomplete = Span::styled(
+                    complete,
+                    if wrong.is_empty() && incomplete.len() <= 1 {
+                        completed_word_style
                     } else {
-                        lines.push(vec![spcomplete, spwrong, spincomplete]);
-                        wordlen
-                    };
-                    (lines, len)
-                },
-            );
+                        completed_part_style
+                    },
+                );
+                let spwrong = Span::styled(wrong, wrong_part_style);
+                let spincomplete = Span::styled(
+                    incomplete.replace('\n', "↵"),
+                    if i == self.enterd_words.len() - 1 {
+                        ongoing_part_style
+                    } else {
+                        incomplete_part_style
+                    },
+                );
+                let spans = vec![spcomplete, spwrong, spincomplete];
+                match ghost {
+                    Some((word, at)) if word == i => mark_char(spans, at, ghost_style),
+                    _ => spans,
+                }
+            })
+            .collect_vec();
 
-        let lines = lines
-            .into_iter()
-            .map(|line| Spans::from(line))
+        // Keep the line being typed in the middle of the viewport, once there is a line above it
+        let starts = self.get_line_starts(width.into());
+        let current = current_line(&starts, self.enterd_words.len() - 1);
+        let first = current.saturating_sub(1);
+        let lines = starts[first..]
+            .iter()
+            .zip(starts[first + 1..].iter().chain(Some(&words.len())))
+            .take(VISIBLE_LINES)
+            .map(|(&from, &to)| {
+                words[from..to]
+                    .iter()
+                    .flatten()
+                    .filter(|span| !span.content.is_empty())
+                    .cloned()
+                    .collect_vec()
+            })
+            .map(Spans::from)
             .collect_vec();
         let par = Paragraph::new(lines);
 
         f.render_widget(par.block(block), size)
     }
 
+    /// Word in the window, and character in that word, the ghost has reached
+    fn ghost_cursor(&self) -> Option<(usize, usize)> {
+        let ghost = self.ghost.as_ref()?;
+        if self.running == TestState::Pre {
+            return None;
+        }
+        let (word, chars) = ghost.position(self.now - self.start);
+        let i = word.checked_sub(self.words_dropped)?;
+        let target = self.get_target_words().nth(i)?;
+        let indent = target.len() - target.trim_start_matches(' ').len();
+        Some((i, (indent + chars).min(target.graphemes(true).count() - 1)))
+    }
+
+    fn menu_widget(&self, f: &mut Frame<Backend>, size: Rect) {
+        let theme = &self.args.theme;
+        let selected = Style::default().fg(theme.highlight_text).bg(theme.menu);
+        let par = Paragraph::new(self.menu.lines(selected)).block(self.block().title("Menu"));
+        f.render_widget(par, size);
+    }
+
+    fn heatmap_widget(&self, f: &mut Frame<Backend>, size: Rect) {
+        let par = Paragraph::new(self.key_errors.lines(&self.args.theme))
+            .block(self.block().title("Errors"));
+        f.render_widget(par, size);
+    }
+
     fn stats_widget(&self, f: &mut Frame<Backend>, size: Rect) {
         let outer = self.block().title("Stats");
         let chunks = Layout::default()
@@ -556,32 +1611,42 @@ impl App {
         let progress = LineGauge::default()
             .gauge_style(
                 Style::default()
-                    .fg(Color::White)
-                    .bg(Color::Black)
+                    .fg(self.args.theme.progress)
+                    .bg(self.args.theme.background)
                     .add_modifier(Modifier::BOLD),
             )
             .line_set(symbols::line::THICK)
             .ratio((self.progress / 100.).min(1.));
         f.render_widget(progress, chunks[0]);
-        let datasets = vec![
+        let mut datasets = vec![
             Dataset::default()
                 .name("accuracy")
                 .marker(symbols::Marker::Braille)
                 .graph_type(GraphType::Line)
-                .style(Style::default().fg(Color::Magenta))
+                .style(Style::default().fg(self.args.theme.accuracy))
                 .data(&self.accuracy_history),
             Dataset::default()
                 .name("wpm")
                 .marker(symbols::Marker::Braille)
                 .graph_type(GraphType::Line)
-                .style(Style::default().fg(Color::Cyan))
+                .style(Style::default().fg(self.args.theme.wpm))
                 .data(&self.wpm_history),
         ];
+        if let Some(ghost) = &self.ghost {
+            datasets.push(
+                Dataset::default()
+                    .name("ghost")
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(self.args.theme.ghost))
+                    .data(ghost.wpm_history()),
+            );
+        }
         let line_graph = Chart::new(datasets)
             .x_axis(
                 Axis::default()
                     // .title(Span::styled("X Axis", Style::default().fg(Color::Red)))
-                    .style(Style::default().fg(Color::White))
+                    .style(Style::default().fg(self.args.theme.border))
                     .bounds([0.0, 100.0])
                     .labels(
                         ["0.0", "50.0", "100.0"]
@@ -594,7 +1659,7 @@ impl App {
             .y_axis(
                 Axis::default()
                     // .title(Span::styled("Y Axis", Style::default().fg(Color::Red)))
-                    .style(Style::default().fg(Color::White))
+                    .style(Style::default().fg(self.args.theme.border))
                     .bounds([self.args.chart_min_wpm as _, self.args.chart_max_wpm as _])
                     .labels(
                         [
@@ -618,7 +1683,11 @@ impl App {
         let frame = self.block();
         let par = Paragraph::new(vec![
             Spans::from(Span::raw(format!("WPM: {:.0}", self.wpm))), //
+            Spans::from(Span::raw(format!("NET: {:.0}", self.net_wpm))), //
+            Spans::from(Span::raw(format!("RAW: {:.0}", self.raw_wpm))), //
             Spans::from(Span::raw(format!("ACC: {:.0}%", self.accuracy))), //
+            Spans::from(Span::raw(format!("CHAR ACC: {:.0}%", self.char_accuracy))), //
+            Spans::from(Span::raw(format!("CONS: {:.0}%", self.consistency))), //
             // Spans::from(Span::raw(format!("PRG: {:.0}%", self.progress))), //
             Spans::from(Span::raw(format!(
                 "TIME {:01.0}:{:02.0}s",
@@ -635,21 +1704,73 @@ impl App {
     fn block(&self) -> tui::widgets::Block<'static> {
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::White))
+            .border_style(Style::default().fg(self.args.theme.border))
             .border_type(BorderType::Rounded)
-            .style(Style::default().bg(Color::Black))
+            .style(
+                Style::default()
+                    .bg(self.args.theme.background)
+                    .fg(self.args.theme.text),
+            )
+    }
+}
+
+/// How steady the per second typing speed was, as 100% minus its coefficient of variation.
+/// 100% means every second was typed at exactly the same speed
+fn consistency(per_second: &[usize]) -> f64 {
+    if per_second.len() < 2 {
+        return 0.;
     }
+    let n = per_second.len() as f64;
+    let mean = per_second.iter().sum::<usize>() as f64 / n;
+    if mean == 0. {
+        return 0.;
+    }
+    let variance = per_second
+        .iter()
+        .map(|&k| (k as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    (100. * (1. - variance.sqrt() / mean)).max(0.)
+}
+
+/// `n` words drawn from `dict`, with punctuation and numbers added
+fn draw_words(
+    dict: &dyn WordSource,
+    sampling: Sampling,
+    rng: &mut ChaCha8Rng,
+    punctuator: &mut Punctuator,
+    n: usize,
+) -> Vec<String> {
+    let chosen = sampling
+        .sampler(dict)
+        .sample_iter(&mut *rng)
+        .take(n)
+        .collect_vec();
+    chosen
+        .into_iter()
+        .map(|i| punctuator.word(dict.word(i), rng))
+        .collect()
 }
 
-/// (complete, wrong, incomplete)
+/// Whether `c` ends a word, a newline ends the last word of a line in code tests
+fn is_separator(c: char) -> bool {
+    c == ' ' || c == '\n'
+}
+
+/// Columns `s` takes up in the test, where a newline is shown as one
+fn columns(s: &str) -> usize {
+    s.width() + s.matches('\n').count()
+}
+
+/// (complete, wrong, incomplete), split between characters as they are shown
 fn merge_word<'a>(target: &'a str, enterd: &'a str) -> (&'a str, &'a str, &'a str) {
-    let first_non_match =
-        izip!(target.char_indices(), enterd.char_indices()).find(|&((_, t), (_, u))| t != u);
+    let first_non_match = izip!(target.grapheme_indices(true), enterd.grapheme_indices(true))
+        .find(|&((_, t), (_, u))| t != u);
     if let Some(((i, _), (j, _))) = first_non_match {
-        let enterd = enterd.strip_suffix(' ').unwrap_or(enterd);
+        let enterd = enterd.strip_suffix(is_separator).unwrap_or(enterd);
         (&enterd[..j], &enterd[j..], &target[i..])
     } else if target.len() >= enterd.len() {
-        let enterd = enterd.strip_suffix(' ').unwrap_or(enterd);
+        let enterd = enterd.strip_suffix(is_separator).unwrap_or(enterd);
         let j = enterd.len();
         (enterd, "", &target[j..])
     } else {
@@ -658,6 +1779,569 @@ fn merge_word<'a>(target: &'a str, enterd: &'a str) -> (&'a str, &'a str, &'a st
     }
 }
 
+/// Restyle the character `at` (counted as shown) of a run of spans, to draw a cursor over it
+fn mark_char(spans: Vec<Span<'_>>, at: usize, style: Style) -> Vec<Span<'_>> {
+    let mut at = Some(at);
+    let mut marked = Vec::with_capacity(spans.len() + 2);
+    for span in spans {
+        let len = span.content.graphemes(true).count();
+        match at {
+            Some(i) if i < len => {
+                let (start, c) = span.content.grapheme_indices(true).nth(i).unwrap();
+                let end = start + c.len();
+                marked.push(Span::styled(span.content[..start].to_owned(), span.style));
+                marked.push(Span::styled(
+                    span.content[start..end].to_owned(),
+                    span.style.patch(style),
+                ));
+                marked.push(Span::styled(span.content[end..].to_owned(), span.style));
+                at = None;
+            }
+            Some(i) => {
+                at = Some(i - len);
+                marked.push(span);
+            }
+            None => marked.push(span),
+        }
+    }
+    marked
+}
+
+/// Greedily fold words of the given (width, ends a line) into lines of `width` columns,
+/// returning the index of the first word of every line
+fn fold_lines(words: impl Iterator<Item = (usize, bool)>, width: usize) -> Vec<usize> {
+    let mut starts = vec![0];
+    words.enumerate().fold(
+        (0, false),
+        |(linelen, newline), (i, (wordlen, ends_line))| {
+            if !newline && (linelen == 0 || linelen + wordlen < width) {
+                (linelen + wordlen, ends_line)
+            } else {
+                starts.push(i);
+                (wordlen, ends_line)
+            }
+        },
+    );
+    starts
+}
+
+/// The line containing `word`, given the first word of every line
+fn current_line(starts: &[usize], word: usize) -> usize {
+    starts.partition_point(|&start| start <= word) - 1
+}
+
 fn lens_to_ranges(start: &mut usize, &end: &usize) -> Option<Range<usize>> {
     Some(std::mem::replace(start, end)..end)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Color;
+
+    /// A clock that only moves when told to
+    #[derive(Debug)]
+    struct ManualClock(Cell<Instant>);
+
+    impl ManualClock {
+        fn advance(&self, by: Duration) {
+            self.0.set(self.0.get() + by);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    /// An app testing `target`, and the clock it runs on
+    fn app(target_type: TargetStringType, target: &str) -> (App, Rc<ManualClock>) {
+        let clock = Rc::new(ManualClock(Cell::new(Instant::now())));
+        let args = Args {
+            target_type,
+            history: None,
+            ..Args::default()
+        };
+        let mut app = App::new(args, Rc::clone(&clock) as _);
+        app.replay_target(target);
+        (app, clock)
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.on_key(KeyEvent::new(code, KeyModifiers::NONE)).unwrap();
+    }
+
+    fn type_str(app: &mut App, text: &str) {
+        text.chars().for_each(|c| press(app, KeyCode::Char(c)));
+    }
+
+    #[test]
+    fn wpm_is_correct_words_per_minute() {
+        let (mut app, clock) = app(TargetStringType::Words(4), "one two three four ");
+        type_str(&mut app, "one two ");
+        clock.advance(Duration::from_secs(30));
+        app.on_tick().unwrap();
+        assert_eq!(app.running, TestState::Running);
+        assert_eq!(app.correct, 2);
+        assert_eq!(app.wpm, 4.);
+        assert_eq!(app.accuracy, 100.);
+        assert_eq!(app.progress, 50.);
+    }
+
+    #[test]
+    fn no_time_passed_is_zero_wpm() {
+        let (mut app, _clock) = app(TargetStringType::Words(2), "one two ");
+        type_str(&mut app, "one ");
+        app.on_tick().unwrap();
+        assert_eq!(app.wpm, 0.);
+        assert_eq!(app.raw_wpm, 0.);
+    }
+
+    #[test]
+    fn accuracy_counts_mistyped_words() {
+        let (mut app, clock) = app(TargetStringType::Words(4), "one two three four ");
+        type_str(&mut app, "one tow thre ");
+        clock.advance(Duration::from_secs(60));
+        app.on_tick().unwrap();
+        assert_eq!(app.correct, 1);
+        assert_eq!(app.wpm, 1.);
+        assert_eq!(app.accuracy, 100. / 3.);
+        assert_eq!(app.progress, 75.);
+    }
+
+    #[test]
+    fn the_word_being_typed_is_not_counted() {
+        let (mut app, clock) = app(TargetStringType::Words(2), "one two ");
+        type_str(&mut app, "on");
+        clock.advance(Duration::from_secs(1));
+        app.on_tick().unwrap();
+        assert_eq!(app.correct, 0);
+        assert_eq!(app.accuracy, 0.);
+        assert_eq!(app.progress, 0.);
+    }
+
+    #[test]
+    fn timed_test_ends_when_time_is_up() {
+        let (mut app, clock) = app(TargetStringType::Timed(10), "one two three ");
+        assert_eq!(app.running, TestState::Pre);
+        type_str(&mut app, "o");
+        assert_eq!(app.running, TestState::Running);
+        clock.advance(Duration::from_secs(5));
+        app.on_tick().unwrap();
+        assert_eq!(app.progress, 50.);
+        assert_eq!(app.running, TestState::Running);
+        clock.advance(Duration::from_secs(5));
+        app.on_tick().unwrap();
+        assert_eq!(app.running, TestState::Post);
+    }
+
+    #[test]
+    fn word_test_ends_on_the_last_correct_word() {
+        let (mut app, clock) = app(TargetStringType::Words(2), "one two ");
+        type_str(&mut app, "one tw");
+        clock.advance(Duration::from_secs(6));
+        type_str(&mut app, "o");
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.correct, 2);
+        assert_eq!(app.wpm, 20.);
+        assert_eq!(app.progress, 100.);
+    }
+
+    #[test]
+    fn clock_stops_after_the_test() {
+        let (mut app, clock) = app(TargetStringType::Words(1), "one ");
+        type_str(&mut app, "one");
+        let wpm = app.wpm;
+        clock.advance(Duration::from_secs(60));
+        app.on_tick().unwrap();
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.wpm, wpm);
+    }
+
+    #[test]
+    fn tab_ends_and_then_restarts() {
+        let (mut app, _clock) = app(TargetStringType::Words(2), "one two ");
+        type_str(&mut app, "one");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.running, TestState::Post);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.running, TestState::Pre);
+    }
+
+    #[test]
+    fn only_complete_tests_become_the_ghost() {
+        let (mut app, clock) = app(TargetStringType::Words(2), "one two ");
+        app.args.ghost = true;
+        type_str(&mut app, "one");
+        clock.advance(Duration::from_secs(1));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.running, TestState::Post);
+        assert!(app.ghosts.is_empty());
+        app.replay_target("one two ");
+        type_str(&mut app, "one");
+        clock.advance(Duration::from_secs(1));
+        type_str(&mut app, " two");
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.ghosts.len(), 1);
+    }
+
+    #[test]
+    fn keys_before_the_start_are_ignored() {
+        let (mut app, _clock) = app(TargetStringType::Words(2), "one two ");
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.running, TestState::Pre);
+        assert!(app.enterd_str.is_empty());
+    }
+
+    #[test]
+    fn code_is_typed_without_its_indentation() {
+        let (mut app, _clock) = app(TargetStringType::Code, "    foo bar\n    baz\n");
+        type_str(&mut app, "foo bar");
+        press(&mut app, KeyCode::Enter);
+        type_str(&mut app, "baz");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.correct, 3);
+        assert_eq!(app.accuracy, 100.);
+    }
+
+    #[test]
+    fn q_only_quits_outside_a_test() {
+        let (mut app, _clock) = app(TargetStringType::Words(1), "quit ");
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(app.command(q), None);
+        assert_eq!(app.command(ctrl_c), Some(Command::Quit));
+        type_str(&mut app, "qu");
+        assert_eq!(app.running, TestState::Running);
+        assert_eq!(app.command(q), None);
+        type_str(&mut app, "it");
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.command(q), Some(Command::Quit));
+    }
+
+    #[test]
+    fn menu_picks_the_next_test() {
+        let (mut app, _clock) = app(TargetStringType::Timed(15), "one two ");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.running, TestState::Menu);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.running, TestState::Pre);
+        assert_eq!(app.target_type, TargetStringType::Words(50));
+        assert_eq!(app.args.dict.name(), "en1k");
+        assert_eq!(app.target_words.len(), 50);
+    }
+
+    #[test]
+    fn esc_restarts_a_running_test() {
+        let (mut app, _clock) = app(TargetStringType::Words(2), "one two ");
+        type_str(&mut app, "on");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.running, TestState::Pre);
+        assert!(app.enterd_str.is_empty());
+    }
+
+    #[test]
+    fn config_file_sets_defaults() {
+        let config = toml::from_str(
+            "[test]\nmode = 'words'\nlength = 50\n\
+             [colors]\nwrong = 'light red'\n\
+             [keys]\nend = ['f5', 'ctrl-e']",
+        )
+        .unwrap();
+        let args = Args::configured(config, None).unwrap();
+        assert_eq!(args.target_type, TargetStringType::Words(50));
+        assert_eq!(args.theme.wrong, Color::LightRed);
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        assert_eq!(
+            args.keys
+                .command(key(KeyCode::F(5), KeyModifiers::NONE), false),
+            Some(Command::End)
+        );
+        assert_eq!(
+            args.keys
+                .command(key(KeyCode::Char('e'), KeyModifiers::CONTROL), false),
+            Some(Command::End)
+        );
+        assert_eq!(
+            args.keys
+                .command(key(KeyCode::Tab, KeyModifiers::NONE), false),
+            None
+        );
+        assert_eq!(
+            args.keys
+                .command(key(KeyCode::Esc, KeyModifiers::NONE), false),
+            Some(Command::Restart)
+        );
+    }
+
+    #[test]
+    fn same_seed_same_words() {
+        let words = |seed| {
+            let args = Args {
+                target_type: TargetStringType::Words(20),
+                seed: Some(seed),
+                history: None,
+                ..Args::default()
+            };
+            let mut app = App::new(args, Rc::new(SystemClock));
+            let first = app.target_str.clone();
+            app.new_test();
+            assert_eq!(app.target_str, first);
+            first
+        };
+        assert_eq!(words(7), words(7));
+        assert_ne!(words(7), words(8));
+    }
+
+    #[test]
+    fn dropped_words_are_generated_again_from_the_seed() {
+        let timed = || {
+            let args = Args {
+                target_type: TargetStringType::Timed(60),
+                sampling: Sampling::Zipf,
+                modifiers: Modifiers {
+                    punctuation: Some(true),
+                    numbers: Some(true),
+                },
+                seed: Some(7),
+                history: None,
+                ..Args::default()
+            };
+            App::new(args, Rc::new(SystemClock))
+        };
+        let mut app = timed();
+        for _ in 0..3 * WINDOW_WORDS {
+            let word = app.get_target_words().nth(app.enterd_words.len() - 1);
+            let word = word.unwrap().to_owned();
+            type_str(&mut app, &word);
+        }
+        assert!(app.words_dropped > LOOKAHEAD_WORDS);
+        let mut fresh = timed();
+        while fresh.target_words.len() < app.words_dropped + app.target_words.len() {
+            fresh.extend_target(LOOKAHEAD_WORDS);
+        }
+        assert_eq!(app.dropped_target() + &app.target_str, fresh.target_str);
+    }
+
+    #[test]
+    fn custom_sources_have_no_challenge_code() {
+        let dir = std::env::temp_dir().join(format!("shelltyper-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("en1k.txt");
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let list: Rc<dyn WordSource> = Rc::new(WordList::load(&path).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (mut app, _clock) = app(TargetStringType::Words(2), "one two ");
+        app.args.dict = Rc::clone(&list);
+        assert_eq!(app.challenge(), None);
+        let menu = Menu::new(
+            app.target_type,
+            false,
+            &list,
+            Sampling::Uniform,
+            Modifiers::default(),
+        );
+        assert!(Rc::ptr_eq(&menu.choice().1, &list));
+
+        app.args.dict = dict::builtin("en1k").unwrap();
+        assert!(app.challenge().is_some());
+        app.target_type = TargetStringType::Quote;
+        app.args.custom_quotes = true;
+        assert_eq!(app.challenge(), None);
+    }
+
+    #[test]
+    fn consistency_of_steady_and_uneven_typing() {
+        assert_eq!(consistency(&[5, 5, 5]), 100.);
+        assert_eq!(consistency(&[5]), 0.);
+        assert_eq!(consistency(&[0, 10]), 0.);
+        assert_eq!(consistency(&[2, 6]), 50.);
+    }
+
+    fn ctrl(app: &mut App, c: char) {
+        app.on_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+            .unwrap();
+    }
+
+    fn typed(app: &App) -> Vec<&str> {
+        app.get_enterd_words().collect()
+    }
+
+    #[test]
+    fn backspace_goes_back_by_correction_policy() {
+        let target = "one two three ";
+        let with = |correction| {
+            let (mut app, _) = app(TargetStringType::Words(3), target);
+            app.args.correction = correction;
+            app
+        };
+
+        let mut app = with(Correction::Incorrect);
+        type_str(&mut app, "one tow ");
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(typed(&app), ["one ", "to"]);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(typed(&app), ["one ", ""]);
+        type_str(&mut app, "two three");
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.correct, 3);
+
+        let mut app = with(Correction::Never);
+        type_str(&mut app, "one tow ");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(typed(&app), ["one ", "tow ", ""]);
+
+        let mut app = with(Correction::Always);
+        type_str(&mut app, "one ");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(typed(&app), ["one"]);
+    }
+
+    #[test]
+    fn ctrl_w_deletes_a_word() {
+        let (mut app, _) = app(TargetStringType::Words(3), "one two three ");
+        type_str(&mut app, "one twx thr");
+        ctrl(&mut app, 'w');
+        assert_eq!(typed(&app), ["one ", "twx ", ""]);
+        // Some terminals send ctrl-h for backspace, so it isn't bound
+        ctrl(&mut app, 'h');
+        assert_eq!(typed(&app), ["one ", "twx ", ""]);
+        // Nothing left of the word, so the incorrect one before it goes
+        ctrl(&mut app, 'w');
+        assert_eq!(typed(&app), ["one ", ""]);
+        // The correct word is kept
+        ctrl(&mut app, 'w');
+        assert_eq!(typed(&app), ["one ", ""]);
+        assert_eq!(app.running, TestState::Running);
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_character() {
+        let (mut app, _) = app(TargetStringType::Words(1), "café ");
+        type_str(&mut app, "caf\u{e8}");
+        press(&mut app, KeyCode::Backspace);
+        type_str(&mut app, "\u{e9}");
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.correct, 1);
+    }
+
+    #[test]
+    fn non_ascii_words_are_typed_and_marked() {
+        let target = "größe ελληνικά привет ";
+        let (mut app, _) = app(TargetStringType::Words(3), target);
+        type_str(&mut app, "grösse ελληνικά прив");
+        let words = app.get_merged_words().collect_vec();
+        assert_eq!(words[0], ("grö", "sse", "ße "));
+        assert_eq!(words[1], ("ελληνικά", "", " "));
+        assert_eq!(words[2], ("прив", "", "ет "));
+        type_str(&mut app, "ет");
+        assert_eq!(app.running, TestState::Post);
+        assert_eq!(app.correct, 2);
+    }
+
+    #[test]
+    fn accents_typed_after_a_letter_are_one_character() {
+        let (mut app, _) = app(TargetStringType::Words(2), "cafe\u{301} x ");
+        type_str(&mut app, "cafe");
+        assert_eq!(app.expected_char(), Some('\u{301}'));
+        type_str(&mut app, "\u{301}");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(typed(&app), ["caf"]);
+    }
+
+    #[test]
+    fn lines_fold_by_display_width() {
+        let (app, _) = app(TargetStringType::Words(4), "日本語 日本語 ab ab ");
+        // Each of the first two words takes 7 columns, the others 3
+        assert_eq!(app.get_line_starts(10), [0, 1, 2]);
+        assert_eq!(columns("日本語 "), 7);
+        assert_eq!(
+            mark_char(vec![Span::raw("e\u{301}日")], 1, Style::default())[1].content,
+            "日"
+        );
+    }
+
+    #[test]
+    fn punctuation_and_numbers_change_the_words() {
+        let words = |modifiers| {
+            let (mut app, _) = app(TargetStringType::Words(200), "");
+            app.args.seed = Some(3);
+            app.args.modifiers = modifiers;
+            app.new_test();
+            app.target_str.clone()
+        };
+        let plain = words(Modifiers::default());
+        assert!(plain.chars().all(|c| c.is_alphabetic() || c == ' '));
+        let punctuated = words(Modifiers {
+            punctuation: Some(true),
+            numbers: Some(true),
+        });
+        assert!(punctuated.starts_with(char::is_uppercase));
+        assert!(punctuated.contains(". ") && punctuated.contains(", "));
+        assert!(punctuated.contains(|c: char| c.is_ascii_digit()));
+        assert_eq!(punctuated.split(' ').count(), plain.split(' ').count());
+        // Every sentence starts with a capital
+        for (end, next) in punctuated.split(' ').tuple_windows() {
+            if end.ends_with(&['.', '?', '!'][..]) && !next.is_empty() {
+                let first = next
+                    .trim_start_matches(&['"', '('][..])
+                    .chars()
+                    .next()
+                    .unwrap();
+                assert!(!first.is_lowercase(), "{} {}", end, next);
+            }
+        }
+    }
+
+    #[test]
+    fn quotes_with_nothing_left_to_type_are_never_picked() {
+        let quote = |text: &str| Quote {
+            text: text.to_owned(),
+            source: "test".to_owned(),
+        };
+        let args = Args {
+            target_type: TargetStringType::Quote,
+            quotes: vec![quote("1984!"), quote("Big brother is watching.")],
+            modifiers: Modifiers {
+                punctuation: Some(false),
+                numbers: Some(false),
+            },
+            history: None,
+            ..Args::default()
+        };
+        let mut app = App::new(args, Rc::new(SystemClock));
+        for seed in 0..20 {
+            app.args.seed = Some(seed);
+            app.new_test();
+            assert_eq!(app.target_str, "big brother is watching ");
+        }
+
+        app.args.quotes.truncate(1);
+        assert!(modifiers::check_quotes(&app.args.quotes, app.args.modifiers).is_err());
+        app.args.modifiers = Modifiers::default();
+        app.new_test();
+        assert_eq!(app.target_str, "1984! ");
+        // Turning both off in the menu keeps it open, rather than typing "1984!" anyway
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.running, TestState::Menu);
+        assert!(app.menu_error.is_some());
+        assert_eq!(app.args.modifiers, Modifiers::default());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::dict::{self, WordSource};
+use crate::modifiers::Modifiers;
+use crate::sampling::Sampling;
+use crate::TargetStringType;
+use crossterm::event::KeyCode;
+use std::rc::Rc;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+
+/// Lengths offered for timed tests, in seconds
+const SECONDS: [usize; 4] = [15, 30, 60, 120];
+/// Lengths offered for word tests
+const WORDS: [usize; 4] = [10, 25, 50, 100];
+/// Ways of sampling words offered, from easy to hard
+const SAMPLINGS: [Sampling; 4] = [
+    Sampling::Top(100),
+    Sampling::Zipf,
+    Sampling::Uniform,
+    Sampling::Hard,
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Row {
+    Mode,
+    Length,
+    Dict,
+    Sampling,
+    Punctuation,
+    Numbers,
+}
+
+/// What the menu did with a key press
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MenuAction {
+    Stay,
+    /// Start a test with the choices made
+    Start,
+    /// Close the menu, forgetting the choices made
+    Cancel,
+}
+
+/// The test chooser, opened with Esc before or after a test
+#[derive(Debug)]
+pub struct Menu {
+    row: usize,
+    /// Every mode that can be picked, each with the length last chosen for it
+    modes: Vec<TargetStringType>,
+    mode: usize,
+    dicts: Vec<Rc<dyn WordSource>>,
+    dict: usize,
+    samplings: Vec<Sampling>,
+    sampling: usize,
+    modifiers: Modifiers,
+}
+
+impl Menu {
+    /// A menu starting from the current test. Code tests are only offered if a file was given,
+    /// and a word list loaded from a file, or sampling not in the menu, next to the usual ones
+    pub fn new(
+        current: TargetStringType,
+        has_code: bool,
+        dict: &Rc<dyn WordSource>,
+        sampling: Sampling,
+        modifiers: Modifiers,
+    ) -> Menu {
+        let mut modes = vec![
+            TargetStringType::Timed(SECONDS[0]),
+            TargetStringType::Words(WORDS[1]),
+            TargetStringType::Quote,
+        ];
+        if has_code {
+            modes.push(TargetStringType::Code);
+        }
+        let mode = modes
+            .iter()
+            .position(|m| std::mem::discriminant(m) == std::mem::discriminant(&current))
+            .unwrap_or(0);
+        modes[mode] = current;
+
+        let mut dicts: Vec<Rc<dyn WordSource>> =
+            dict::BUILTINS.iter().map(dict::Builtin::load).collect();
+        // A list loaded from a file stays in the menu even if it shares a builtin's name
+        let builtin = dict
+            .builtin()
+            .and_then(|b| dict::BUILTINS.iter().position(|d| std::ptr::eq(d, b)));
+        let chosen = match builtin {
+            Some(i) => i,
+            None => {
+                dicts.push(Rc::clone(dict));
+                dicts.len() - 1
+            }
+        };
+        let mut samplings = SAMPLINGS.to_vec();
+        if !samplings.contains(&sampling) {
+            samplings.push(sampling);
+        }
+        Menu {
+            row: 0,
+            modes,
+            mode,
+            dicts,
+            dict: chosen,
+            sampling: samplings.iter().position(|&s| s == sampling).unwrap(),
+            samplings,
+            modifiers,
+        }
+    }
+
+    /// The test that was picked, and the dictionary to generate it from and how
+    pub fn choice(&self) -> (TargetStringType, Rc<dyn WordSource>, Sampling, Modifiers) {
+        (
+            self.modes[self.mode],
+            Rc::clone(&self.dicts[self.dict]),
+            self.samplings[self.sampling],
+            self.modifiers,
+        )
+    }
+
+    /// Rows that apply to the mode picked, quotes have no length or dictionary and code
+    /// is typed as it is
+    fn rows(&self) -> Vec<Row> {
+        match self.modes[self.mode] {
+            TargetStringType::Timed(_) | TargetStringType::Words(_) => vec![
+                Row::Mode,
+                Row::Length,
+                Row::Dict,
+                Row::Sampling,
+                Row::Punctuation,
+                Row::Numbers,
+            ],
+            TargetStringType::Quote => vec![Row::Mode, Row::Punctuation, Row::Numbers],
+            TargetStringType::Code => vec![Row::Mode],
+        }
+    }
+
+    /// Up and down pick a row, left and right change it
+    pub fn on_key(&mut self, key: KeyCode) -> MenuAction {
+        let rows = self.rows();
+        match key {
+            KeyCode::Up => self.row = self.row.saturating_sub(1),
+            KeyCode::Down => self.row = (self.row + 1).min(rows.len() - 1),
+            KeyCode::Left | KeyCode::Right => {
+                let forward = key == KeyCode::Right;
+                match rows[self.row] {
+                    Row::Mode => self.mode = cycle(self.mode, self.modes.len(), forward),
+                    Row::Dict => self.dict = cycle(self.dict, self.dicts.len(), forward),
+                    Row::Sampling => {
+                        self.sampling = cycle(self.sampling, self.samplings.len(), forward)
+                    }
+                    Row::Punctuation | Row::Numbers => {
+                        let (punctuation, numbers) = self.modifiers.of(self.modes[self.mode]);
+                        if rows[self.row] == Row::Punctuation {
+                            self.modifiers.punctuation = Some(!punctuation);
+                        } else {
+                            self.modifiers.numbers = Some(!numbers);
+                        }
+                    }
+                    Row::Length => {
+                        let mode = &mut self.modes[self.mode];
+                        *mode = match *mode {
+                            TargetStringType::Timed(n) => {
+                                TargetStringType::Timed(step(&SECONDS, n, forward))
+                            }
+                            TargetStringType::Words(n) => {
+                                TargetStringType::Words(step(&WORDS, n, forward))
+                            }
+                            other => other,
+                        }
+                    }
+                }
+            }
+            KeyCode::Enter => return MenuAction::Start,
+            KeyCode::Esc => return MenuAction::Cancel,
+            _ => {}
+        }
+        MenuAction::Stay
+    }
+
+    /// One line per row, with the selected row highlighted
+    pub fn lines(&self, selected: Style) -> Vec<Spans<'static>> {
+        let modifiers = self.modifiers.of(self.modes[self.mode]);
+        self.rows()
+            .iter()
+            .enumerate()
+            .map(|(i, &row)| {
+                let (name, value) = match row {
+                    Row::Mode => ("Mode", mode_name(self.modes[self.mode]).to_owned()),
+                    Row::Length => ("Length", length(self.modes[self.mode])),
+                    Row::Dict => ("Dictionary", self.dicts[self.dict].name().to_owned()),
+                    Row::Sampling => ("Sampling", self.samplings[self.sampling].to_string()),
+                    Row::Punctuation => ("Punctuation", on_off(modifiers.0)),
+                    Row::Numbers => ("Numbers", on_off(modifiers.1)),
+                };
+                let value = Span::styled(
+                    format!("< {} >", value),
+                    if i == self.row {
+                        selected
+                    } else {
+                        Style::default()
+                    },
+                );
+                Spans::from(vec![Span::raw(format!("{:>12}  ", name)), value])
+            })
+            .chain(Some(Spans::from(Span::styled(
+                "   up/down: choose   left/right: change   enter: start   esc: back",
+                Style::default().add_modifier(Modifier::DIM),
+            ))))
+            .collect()
+    }
+}
+
+fn mode_name(mode: TargetStringType) -> &'static str {
+    match mode {
+        TargetStringType::Timed(_) => "timed",
+        TargetStringType::Words(_) => "words",
+        TargetStringType::Quote => "quote",
+        TargetStringType::Code => "code",
+    }
+}
+
+fn length(mode: TargetStringType) -> String {
+    match mode {
+        TargetStringType::Timed(n) => format!("{} seconds", n),
+        TargetStringType::Words(n) => format!("{} words", n),
+        TargetStringType::Quote | TargetStringType::Code => String::new(),
+    }
+}
+
+fn on_off(on: bool) -> String {
+    if on { "on" } else { "off" }.to_owned()
+}
+
+/// The next index of `len` options in either direction, wrapping around
+fn cycle(i: usize, len: usize, forward: bool) -> usize {
+    if forward {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }
+}
+
+/// The next option after `current`, which may be a length given on the command line
+/// that isn't one of the options
+fn step(options: &[usize], current: usize, forward: bool) -> usize {
+    if forward {
+        options.iter().copied().find(|&n| n > current)
+    } else {
+        options.iter().copied().rev().find(|&n| n < current)
+    }
+    .unwrap_or(current)
+}
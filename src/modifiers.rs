@@ -0,0 +1,165 @@
+use crate::quotes::Quote;
+use crate::TargetStringType;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Monkeytype's punctuation and numbers toggles. Left unset, words from a dictionary get
+/// neither and quotes keep what they have
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Modifiers {
+    pub punctuation: Option<bool>,
+    pub numbers: Option<bool>,
+}
+
+impl Modifiers {
+    /// (punctuation, numbers) as they apply to tests of `mode`
+    pub fn of(self, mode: TargetStringType) -> (bool, bool) {
+        let quote = mode == TargetStringType::Quote;
+        (
+            self.punctuation.unwrap_or(quote),
+            self.numbers.unwrap_or(quote),
+        )
+    }
+}
+
+/// Chance of a word being a number instead
+const NUMBER: f64 = 0.1;
+/// Chances of a word being put in quotes or parentheses
+const QUOTED: f64 = 0.02;
+const PARENTHESIZED: f64 = 0.02;
+/// Chances of a word being followed by each mark, the first three end a sentence
+const MARKS: [(char, f64); 6] = [
+    ('.', 0.07),
+    ('?', 0.02),
+    ('!', 0.01),
+    (',', 0.08),
+    (';', 0.01),
+    (':', 0.01),
+];
+
+/// Adds punctuation and numbers to words from a dictionary, one word at a time so that
+/// sentences carry on as the target is extended
+#[derive(Debug, Default)]
+pub struct Punctuator {
+    punctuation: bool,
+    numbers: bool,
+    /// Whether the previous word ended a sentence
+    ended: bool,
+}
+
+impl Punctuator {
+    pub fn new(punctuation: bool, numbers: bool) -> Punctuator {
+        Punctuator {
+            punctuation,
+            numbers,
+            ended: true,
+        }
+    }
+
+    pub fn word<R: Rng + ?Sized>(&mut self, word: &str, rng: &mut R) -> String {
+        let mut word = if self.numbers && rng.gen_bool(NUMBER) {
+            rng.gen_range(0..10_000).to_string()
+        } else {
+            word.to_owned()
+        };
+        if !self.punctuation {
+            return word;
+        }
+        if self.ended {
+            word = capitalize(&word);
+        }
+        let wrap: f64 = rng.gen();
+        if wrap < QUOTED {
+            word = format!("\"{}\"", word);
+        } else if wrap < QUOTED + PARENTHESIZED {
+            word = format!("({})", word);
+        }
+        let mut mark: f64 = rng.gen();
+        self.ended = false;
+        for (i, &(c, chance)) in MARKS.iter().enumerate() {
+            if mark < chance {
+                word.push(c);
+                self.ended = i < 3;
+                break;
+            }
+            mark -= chance;
+        }
+        word
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The words of a quote, without its punctuation and capitals or numbers if those are off
+pub fn quote_words(text: &str, punctuation: bool, numbers: bool) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            let word: String = word
+                .char_indices()
+                .filter(|&(i, c)| {
+                    if c.is_numeric() {
+                        numbers
+                    } else if c.is_alphabetic() {
+                        true
+                    } else {
+                        // Keep apostrophes and hyphens inside a word, like "don't"
+                        punctuation
+                            || (matches!(c, '\'' | '’' | '-')
+                                && word[..i].ends_with(char::is_alphabetic)
+                                && word[i + c.len_utf8()..].starts_with(char::is_alphabetic))
+                    }
+                })
+                .map(|(_, c)| c)
+                .collect();
+            if punctuation {
+                word
+            } else {
+                word.to_lowercase()
+            }
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Indices of the quotes that still have words once `modifiers` are applied. A quote like
+/// "1984!" has none left without punctuation and numbers
+pub fn typeable_quotes(quotes: &[Quote], modifiers: Modifiers) -> Vec<usize> {
+    let (punctuation, numbers) = modifiers.of(TargetStringType::Quote);
+    (0..quotes.len())
+        .filter(|&i| !quote_words(&quotes[i].text, punctuation, numbers).is_empty())
+        .collect()
+}
+
+/// Why a quote test can't start with `modifiers`, if it can't
+pub fn check_quotes(quotes: &[Quote], modifiers: Modifiers) -> Result<(), String> {
+    if typeable_quotes(quotes, modifiers).is_empty() {
+        Err("no quote has any words left without its punctuation and numbers".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_lose_punctuation_and_numbers_when_turned_off() {
+        let text = "Don't panic, it's 42 (self-evident) \"facts\".";
+        assert_eq!(quote_words(text, true, true).join(" "), text);
+        assert_eq!(
+            quote_words(text, false, false),
+            ["don't", "panic", "it's", "self-evident", "facts"]
+        );
+        assert_eq!(
+            Modifiers::default().of(TargetStringType::Quote),
+            (true, true)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use std::path::Path;
+
+/// A passage to type, with where it came from
+#[derive(Debug, Clone, Deserialize)]
+pub struct Quote {
+    pub text: String,
+    pub source: String,
+}
+
+/// (text, source)
+static BUNDLED: [(&str, &str); 24] = [
+    (
+        "It was the best of times, it was the worst of times, it was the age of wisdom, it was the age of foolishness.",
+        "Charles Dickens, A Tale of Two Cities",
+    ),
+    (
+        "It is a truth universally acknowledged, that a single man in possession of a good fortune, must be in want of a wife.",
+        "Jane Austen, Pride and Prejudice",
+    ),
+    (
+        "Call me Ishmael. Some years ago, never mind how long precisely, having little or no money in my purse, and nothing particular to interest me on shore, I thought I would sail about a little and see the watery part of the world.",
+        "Herman Melville, Moby-Dick",
+    ),
+    (
+        "Happy families are all alike; every unhappy family is unhappy in its own way.",
+        "Leo Tolstoy, Anna Karenina",
+    ),
+    (
+        "All that is gold does not glitter, not all those who wander are lost.",
+        "J. R. R. Tolkien, The Fellowship of the Ring",
+    ),
+    (
+        "I took a deep breath and listened to the old brag of my heart: I am, I am, I am.",
+        "Sylvia Plath, The Bell Jar",
+    ),
+    (
+        "We hold these truths to be self-evident, that all men are created equal.",
+        "The Declaration of Independence",
+    ),
+    (
+        "The only thing we have to fear is fear itself.",
+        "Franklin D. Roosevelt",
+    ),
+    (
+        "Two roads diverged in a wood, and I, I took the one less traveled by, and that has made all the difference.",
+        "Robert Frost, The Road Not Taken",
+    ),
+    (
+        "To be, or not to be, that is the question: whether 'tis nobler in the mind to suffer the slings and arrows of outrageous fortune, or to take arms against a sea of troubles.",
+        "William Shakespeare, Hamlet",
+    ),
+    (
+        "In the beginning the Universe was created. This has made a lot of people very angry and been widely regarded as a bad move.",
+        "Douglas Adams, The Restaurant at the End of the Universe",
+    ),
+    (
+        "Programs must be written for people to read, and only incidentally for machines to execute.",
+        "Harold Abelson, Structure and Interpretation of Computer Programs",
+    ),
+    (
+        "Simplicity is prerequisite for reliability.",
+        "Edsger W. Dijkstra",
+    ),
+    (
+        "There are only two hard things in Computer Science: cache invalidation and naming things.",
+        "Phil Karlton",
+    ),
+    (
+        "Premature optimization is the root of all evil.",
+        "Donald Knuth",
+    ),
+    (
+        "Any fool can write code that a computer can understand. Good programmers write code that humans can understand.",
+        "Martin Fowler, Refactoring",
+    ),
+    (
+        "The best way to predict the future is to invent it.",
+        "Alan Kay",
+    ),
+    (
+        "Whereof one cannot speak, thereof one must be silent.",
+        "Ludwig Wittgenstein, Tractatus Logico-Philosophicus",
+    ),
+    (
+        "The journey of a thousand miles begins with a single step.",
+        "Lao Tzu, Tao Te Ching",
+    ),
+    (
+        "It was a bright cold day in April, and the clocks were striking thirteen.",
+        "George Orwell, Nineteen Eighty-Four",
+    ),
+    (
+        "The sky above the port was the color of television, tuned to a dead channel.",
+        "William Gibson, Neuromancer",
+    ),
+    (
+        "Whether you think you can, or you think you can't, you're right.",
+        "Henry Ford",
+    ),
+    (
+        "Be yourself; everyone else is already taken.",
+        "Oscar Wilde",
+    ),
+    (
+        "So we beat on, boats against the current, borne back ceaselessly into the past.",
+        "F. Scott Fitzgerald, The Great Gatsby",
+    ),
+];
+
+/// The quotes compiled into the binary
+pub fn bundled() -> Vec<Quote> {
+    BUNDLED
+        .iter()
+        .map(|&(text, source)| Quote {
+            text: text.to_owned(),
+            source: source.to_owned(),
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum QuotesError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Index of a quote with no text
+    EmptyQuote(usize),
+    Empty,
+}
+
+impl Display for QuotesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotesError::Io(e) => write!(f, "{}", e),
+            QuotesError::Json(e) => write!(f, "invalid JSON: {}", e),
+            QuotesError::EmptyQuote(i) => write!(f, "quote {} has no text", i),
+            QuotesError::Empty => write!(f, "the quote collection is empty"),
+        }
+    }
+}
+
+impl std::error::Error for QuotesError {}
+
+/// Load a JSON array of `{"text": ..., "source": ...}` objects
+pub fn load(path: &Path) -> Result<Vec<Quote>, QuotesError> {
+    let text = std::fs::read_to_string(path).map_err(QuotesError::Io)?;
+    let quotes: Vec<Quote> = serde_json::from_str(&text).map_err(QuotesError::Json)?;
+    if let Some(i) = quotes.iter().position(|q| q.text.trim().is_empty()) {
+        return Err(QuotesError::EmptyQuote(i));
+    }
+    if quotes.is_empty() {
+        return Err(QuotesError::Empty);
+    }
+    Ok(quotes)
+}
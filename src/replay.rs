@@ -0,0 +1,346 @@
+use crate::clock::VirtualClock;
+use crate::history::{self, Record};
+use crate::keys::Command;
+use crate::stats::format_date;
+use crate::{
+    config, dict, input_handling_thread, setup_terminal, App, Args, Backend, Event, TestState,
+};
+use crossterm::event::KeyCode;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::Style;
+use tui::text::{Span, Spans};
+use tui::widgets::Paragraph;
+use tui::Frame;
+
+const HELP: &str = "\
+Watch a past test again, keystroke by keystroke
+
+Usage: shelltyper replay [OPTIONS] [FILE]
+
+    FILE is a history file [default: $XDG_DATA_HOME/shelltyper/history.jsonl]
+
+    -n, --test             NUM        Which test in the file to replay, counting from 1, or
+                                      back from the last one if negative [default: -1]
+        --speed            SPEED      Play at 1, 2 or 4 times the real speed [default: 1]
+
+While playing: space pauses, 1, 2 and 4 set the speed, left and right seek 5 seconds,
+home restarts and q quits
+";
+
+/// How far the arrow keys seek
+const SEEK_STEP: Duration = Duration::from_secs(5);
+/// Longest stretch of virtual time between two ticks of the app
+const TICK: Duration = Duration::from_millis(1000 / 60);
+
+/// Run `shelltyper replay`
+pub fn run(mut pargs: pico_args::Arguments) -> Result<(), Box<dyn Error>> {
+    if pargs.contains(["-h", "--help"]) {
+        print!("{}", HELP);
+        return Ok(());
+    }
+    let test: i64 = pargs.opt_value_from_str(["-n", "--test"])?.unwrap_or(-1);
+    let speed: u32 = pargs.opt_value_from_str("--speed")?.unwrap_or(1);
+    if ![1, 2, 4].contains(&speed) {
+        return Err("the speed must be 1, 2 or 4".into());
+    }
+    let path = match pargs.opt_free_from_str::<PathBuf>()? {
+        Some(path) => path,
+        None => history::default_path().ok_or("can't find the history file, $HOME is not set")?,
+    };
+
+    let mut records = history::load(&path)?;
+    let count = records.len();
+    let index = if test < 0 {
+        count as i64 + test
+    } else {
+        test - 1
+    };
+    let record = match usize::try_from(index) {
+        Ok(i) if i < count => records.swap_remove(i),
+        _ => {
+            return Err(format!(
+                "{}: there is no test {} among {} tests",
+                path.display(),
+                test,
+                count
+            )
+            .into())
+        }
+    };
+    if record.keystrokes.is_empty() || record.target.is_empty() {
+        return Err(format!(
+            "{}: test {} was saved without its keystrokes, so it can't be replayed",
+            path.display(),
+            test
+        )
+        .into());
+    }
+
+    // Keys and colors come from the config file, the test itself from the record
+    let configured = match config::default_path() {
+        Some(path) => config::load(&path)
+            .and_then(|config| Args::configured(config, None))
+            .map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => Args::default(),
+    };
+    let dict = match record.dict.as_deref().map(dict::builtin) {
+        Some(Ok(dict)) => dict,
+        _ => Rc::clone(&configured.dict),
+    };
+    let args = Args {
+        target_type: record.mode,
+        dict,
+        seed: record.seed,
+        sampling: record.sampling,
+        modifiers: record.modifiers,
+        correction: record.correction,
+        history: None,
+        ghost: false,
+        ..configured
+    };
+    let mut replay = Replay::new(record, args);
+    replay.clock.set_speed(speed);
+    replay.clock.set_paused(false);
+
+    let mut terminal = setup_terminal()?;
+    let rx = input_handling_thread(&terminal);
+
+    loop {
+        terminal.draw(|f| replay.draw(f))?;
+
+        match rx.recv()? {
+            Event::Tick => {
+                replay.advance_to(replay.clock.elapsed())?;
+                if replay.finished() {
+                    replay.clock.set_paused(true);
+                }
+            }
+            Event::Input(key)
+                if replay.app.args.keys.command(key, false) == Some(Command::Quit) =>
+            {
+                break
+            }
+            Event::Input(key) => match key.code {
+                KeyCode::Esc => break,
+                KeyCode::Char(' ') if replay.finished() => {
+                    replay.seek(Duration::from_secs(0))?;
+                    replay.clock.set_paused(false);
+                }
+                KeyCode::Char(' ') => replay.clock.set_paused(!replay.clock.is_paused()),
+                KeyCode::Char(c @ '1') | KeyCode::Char(c @ '2') | KeyCode::Char(c @ '4') => {
+                    replay.clock.set_speed(c.to_digit(10).unwrap())
+                }
+                KeyCode::Left => replay.seek(replay.at.saturating_sub(SEEK_STEP))?,
+                KeyCode::Right => replay.seek(replay.at + SEEK_STEP)?,
+                KeyCode::Home => replay.seek(Duration::from_secs(0))?,
+                _ => {}
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// A recorded test played back through an `App` running on a virtual clock
+#[derive(Debug)]
+struct Replay {
+    record: Record,
+    app: App,
+    clock: Rc<VirtualClock>,
+    /// Index of the next keystroke to play
+    next: usize,
+    /// Virtual time the app has been brought up to
+    at: Duration,
+}
+
+impl Replay {
+    fn new(record: Record, args: Args) -> Replay {
+        let clock = Rc::new(VirtualClock::new());
+        let mut app = App::new(args, Rc::clone(&clock) as _);
+        app.replay_target(&record.target);
+        Replay {
+            record,
+            app,
+            clock,
+            next: 0,
+            at: Duration::from_secs(0),
+        }
+    }
+
+    /// Time from the first key to the end of the test
+    fn length(&self) -> Duration {
+        let last_key = self.record.keystrokes.last().map_or(0, |k| k.time);
+        Duration::from_secs_f64(self.record.duration).max(Duration::from_micros(last_key))
+    }
+
+    fn finished(&self) -> bool {
+        self.next == self.record.keystrokes.len() && self.app.running == TestState::Post
+    }
+
+    /// Play every keystroke up to `to`, ticking the app at least as often as it is
+    /// ticked live so the charts come out the same
+    fn advance_to(&mut self, to: Duration) -> Result<(), Box<dyn Error>> {
+        loop {
+            let step = (self.at + TICK).min(to);
+            while let Some(keystroke) = self
+                .record
+                .keystrokes
+                .get(self.next)
+                .filter(|k| Duration::from_micros(k.time) <= step)
+            {
+                let key = keystroke.key;
+                self.clock.seek(Duration::from_micros(keystroke.time));
+                self.app.on_key(key)?;
+                self.next += 1;
+            }
+            self.clock.seek(step);
+            self.app.on_tick()?;
+            self.at = step;
+            if step >= to {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Jump to `to`. Going backwards plays the test again from the start
+    fn seek(&mut self, to: Duration) -> Result<(), Box<dyn Error>> {
+        let to = to.min(self.length());
+        if to < self.at {
+            self.app.replay_target(&self.record.target);
+            self.next = 0;
+            self.at = Duration::from_secs(0);
+        }
+        self.advance_to(to)
+    }
+
+    fn draw(&self, f: &mut Frame<Backend>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+        self.app.draw(f, chunks[0]);
+
+        let state = if self.finished() {
+            "Finished"
+        } else if self.clock.is_paused() {
+            "Paused"
+        } else {
+            "Playing"
+        };
+        let mut about = self.record.mode.to_string();
+        if let Some(dict) = &self.record.dict {
+            about = format!("{} {}", about, dict);
+        }
+        about = format!("{} on {}", about, format_date(self.record.timestamp));
+        if let Some(source) = &self.record.source {
+            about = format!("{} - {}", about, source);
+        }
+        let status = Paragraph::new(Spans::from(vec![
+            Span::styled(
+                format!(" {} ", state),
+                Style::default()
+                    .fg(self.app.args.theme.highlight_text)
+                    .bg(self.app.args.theme.ready),
+            ),
+            Span::raw(format!(
+                " {} / {}  {}x  {}",
+                format_time(self.at),
+                format_time(self.length()),
+                self.clock.speed(),
+                about
+            )),
+        ]));
+        let keys = Paragraph::new("space pause  1 2 4 speed  ←→ seek  home restart  q quit ")
+            .alignment(Alignment::Right);
+        f.render_widget(status, chunks[1]);
+        f.render_widget(keys, chunks[1]);
+    }
+}
+
+/// `1:02.3`
+fn format_time(t: Duration) -> String {
+    let secs = t.as_secs_f64();
+    format!("{}:{:04.1}", t.as_secs() / 60, secs % 60.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Keystroke;
+    use crate::TargetStringType;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    /// A replay of "one two" typed a key every 100ms from the start
+    fn replay() -> Replay {
+        let mode = TargetStringType::Words(2);
+        let mut record: Record = serde_json::from_value(serde_json::json!({
+            "version": history::VERSION,
+            "timestamp": 0,
+            "mode": mode,
+            "wpm": 200.0,
+            "accuracy": 100.0,
+            "correct": 2,
+            "duration": 0.6,
+            "wpm_history": [],
+            "accuracy_history": [],
+            "target": "one two ",
+        }))
+        .unwrap();
+        record.keystrokes = "one two"
+            .chars()
+            .enumerate()
+            .map(|(i, c)| Keystroke {
+                time: i as u64 * 100_000,
+                key: KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+                accepted: true,
+            })
+            .collect();
+        let args = Args {
+            target_type: mode,
+            history: None,
+            ..Args::default()
+        };
+        Replay::new(record, args)
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn keys_are_played_when_their_time_comes() {
+        let mut replay = replay();
+        assert_eq!(replay.app.running, TestState::Pre);
+        replay.advance_to(ms(250)).unwrap();
+        assert_eq!(replay.app.enterd_str, "one");
+        assert_eq!(replay.app.running, TestState::Running);
+        replay.advance_to(ms(300)).unwrap();
+        assert_eq!(replay.app.enterd_str, "one ");
+        assert!(!replay.finished());
+
+        replay.advance_to(replay.length()).unwrap();
+        assert!(replay.finished());
+        assert_eq!(replay.app.correct, 2);
+        assert!((replay.app.wpm - replay.record.wpm).abs() < 1e-9);
+    }
+
+    #[test]
+    fn seeking_back_plays_the_test_again_from_the_start() {
+        let mut replay = replay();
+        replay.seek(ms(10_000)).unwrap();
+        assert_eq!(replay.at, replay.length());
+        assert!(replay.finished());
+
+        replay.seek(ms(150)).unwrap();
+        assert_eq!((replay.next, replay.at), (2, ms(150)));
+        assert_eq!(replay.app.enterd_str, "on");
+        assert_eq!(replay.app.running, TestState::Running);
+        replay.seek(ms(0)).unwrap();
+        assert_eq!(replay.app.enterd_str, "o");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::dict::WordSource;
+use rand::distributions::{Distribution, Uniform, WeightedIndex};
+use rand::Rng;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// How often each word of a (frequency ordered) list is picked
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Sampling {
+    /// Every word as often as any other
+    #[default]
+    Uniform,
+    /// As often as in real text, so "the" comes up far more than rare words
+    Zipf,
+    /// Only the `n` most common words
+    Top(usize),
+    /// Mostly long and rare words
+    Hard,
+}
+
+impl fmt::Display for Sampling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sampling::Uniform => write!(f, "uniform"),
+            Sampling::Zipf => write!(f, "zipf"),
+            Sampling::Top(n) => write!(f, "top{}", n),
+            Sampling::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+impl FromStr for Sampling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(Sampling::Uniform),
+            "zipf" => Ok(Sampling::Zipf),
+            "hard" => Ok(Sampling::Hard),
+            _ => match s.strip_prefix("top").map(str::parse) {
+                Some(Ok(n)) if n > 0 => Ok(Sampling::Top(n)),
+                _ => Err(format!(
+                    "unknown sampling '{}', expected uniform, zipf, top<N> like top100, or hard",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// Written as its name, like `top100`, in the config file and the history
+impl Serialize for Sampling {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sampling {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(D::Error::custom)
+    }
+}
+
+/// Picks indices of words from a list, see `Sampling::sampler`
+#[derive(Debug)]
+pub enum Sampler {
+    Uniform(Uniform<usize>),
+    Weighted(WeightedIndex<f64>),
+}
+
+impl Distribution<usize> for Sampler {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match self {
+            Sampler::Uniform(uniform) => uniform.sample(rng),
+            Sampler::Weighted(weighted) => weighted.sample(rng),
+        }
+    }
+}
+
+impl Sampling {
+    pub fn sampler(self, dict: &dyn WordSource) -> Sampler {
+        let len = dict.len();
+        let weighted = |weight: &dyn Fn(usize) -> f64| {
+            Sampler::Weighted(WeightedIndex::new((0..len).map(weight)).unwrap())
+        };
+        match self {
+            Sampling::Uniform => Sampler::Uniform(Uniform::from(0..len)),
+            Sampling::Top(n) => Sampler::Uniform(Uniform::from(0..n.min(len))),
+            // The i-th most common word of a language turns up about 1/i as often as the first
+            Sampling::Zipf => weighted(&|i| 1. / (i + 1) as f64),
+            // Twice as likely at the end of the list as at the start, and by the square of
+            // the length
+            Sampling::Hard => weighted(&|i| {
+                let chars = dict.word(i).chars().count() as f64;
+                chars * chars * (1. + i as f64 / len as f64)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn sampling_modes_pick_easier_or_harder_words() {
+        let dict = dict::builtin("en1k").unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut sample = |sampling: Sampling| {
+            let sampler = sampling.sampler(&*dict);
+            (0..2000)
+                .map(|_| sampler.sample(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert!(sample(Sampling::Top(50)).iter().all(|&i| i < 50));
+        let common = |picks: &[usize]| picks.iter().filter(|&&i| i < 10).count();
+        assert!(common(&sample(Sampling::Zipf)) > 4 * common(&sample(Sampling::Uniform)));
+        let length = |picks: &[usize]| picks.iter().map(|&i| dict.word(i).len()).sum::<usize>();
+        assert!(length(&sample(Sampling::Hard)) > length(&sample(Sampling::Uniform)));
+
+        assert_eq!("top100".parse(), Ok(Sampling::Top(100)));
+        assert!("top".parse::<Sampling>().is_err());
+        let json = serde_json::to_string(&Sampling::Top(100)).unwrap();
+        assert_eq!(json, "\"top100\"");
+        assert_eq!(
+            serde_json::from_str::<Sampling>(&json).unwrap(),
+            Sampling::Top(100)
+        );
+    }
+}
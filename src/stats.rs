@@ -0,0 +1,284 @@
+use crate::history::{self, Record};
+use crate::TargetStringType;
+use serde::Serialize;
+use std::path::PathBuf;
+
+const HELP: &str = "\
+Summarise the results of past tests
+
+Usage: shelltyper stats [OPTIONS]
+
+        --json                        Print the summary as JSON
+        --history          PATH       History file to read
+                                      [default: $XDG_DATA_HOME/shelltyper/history.jsonl]
+";
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    tests: usize,
+    /// Seconds spent typing over all tests
+    total_time: f64,
+    personal_bests: Vec<Best>,
+    last_10: Option<Average>,
+    last_100: Option<Average>,
+    /// Average accuracy of the last 10 tests minus that of the last 100,
+    /// once there are more than 10 tests
+    accuracy_trend: Option<f64>,
+}
+
+/// The fastest complete test of one mode and duration
+#[derive(Debug, Serialize)]
+struct Best {
+    mode: TargetStringType,
+    wpm: f64,
+    accuracy: f64,
+    timestamp: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct Average {
+    tests: usize,
+    wpm: f64,
+    accuracy: f64,
+}
+
+/// Run `shelltyper stats`
+pub fn run(mut pargs: pico_args::Arguments) -> Result<(), Box<dyn std::error::Error>> {
+    if pargs.contains(["-h", "--help"]) {
+        print!("{}", HELP);
+        return Ok(());
+    }
+    let json = pargs.contains("--json");
+    let path = match pargs.opt_value_from_str::<_, PathBuf>("--history")? {
+        Some(path) => path,
+        None => history::default_path().ok_or("can't find the history file, $HOME is not set")?,
+    };
+
+    let summary = summarise(&history::load(&path)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        print_table(&summary);
+    }
+    Ok(())
+}
+
+fn summarise(records: &[Record]) -> Summary {
+    let mut personal_bests: Vec<Best> = Vec::new();
+    // A test ended early can have a speed no whole test would reach
+    for record in records.iter().filter(|r| r.completed) {
+        match personal_bests.iter_mut().find(|b| b.mode == record.mode) {
+            Some(best) if best.wpm >= record.wpm => {}
+            Some(best) => *best = Best::from(record),
+            None => personal_bests.push(Best::from(record)),
+        }
+    }
+    personal_bests.sort_by_key(|b| mode_order(b.mode));
+
+    let last_10 = average(records, 10);
+    let last_100 = average(records, 100);
+    Summary {
+        tests: records.len(),
+        // Summed from 0. so an empty history doesn't give -0.
+        total_time: records.iter().fold(0., |total, r| total + r.duration),
+        personal_bests,
+        accuracy_trend: match (&last_10, &last_100) {
+            (Some(recent), Some(longer)) if longer.tests > recent.tests => {
+                Some(recent.accuracy - longer.accuracy)
+            }
+            _ => None,
+        },
+        last_10,
+        last_100,
+    }
+}
+
+impl From<&Record> for Best {
+    fn from(record: &Record) -> Self {
+        Best {
+            mode: record.mode,
+            wpm: record.wpm,
+            accuracy: record.accuracy,
+            timestamp: record.timestamp,
+        }
+    }
+}
+
+/// Group timed tests, then word tests, each by length, then quotes and code
+fn mode_order(mode: TargetStringType) -> (usize, usize) {
+    match mode {
+        TargetStringType::Timed(n) => (0, n),
+        TargetStringType::Words(n) => (1, n),
+        TargetStringType::Quote => (2, 0),
+        TargetStringType::Code => (3, 0),
+    }
+}
+
+/// Average of the last `n` complete tests
+fn average(records: &[Record], n: usize) -> Option<Average> {
+    let complete: Vec<&Record> = records.iter().filter(|r| r.completed).collect();
+    let recent = &complete[complete.len().saturating_sub(n)..];
+    if recent.is_empty() {
+        return None;
+    }
+    let count = recent.len() as f64;
+    Some(Average {
+        tests: recent.len(),
+        wpm: recent.iter().map(|r| r.wpm).sum::<f64>() / count,
+        accuracy: recent.iter().map(|r| r.accuracy).sum::<f64>() / count,
+    })
+}
+
+fn print_table(summary: &Summary) {
+    println!(
+        "Tests: {}    Time typing: {}",
+        summary.tests,
+        format_duration(summary.total_time)
+    );
+    if summary.tests == 0 {
+        return;
+    }
+
+    println!();
+    println!("Personal bests");
+    println!("  {:<12} {:>6} {:>6}  date", "mode", "wpm", "acc");
+    for best in &summary.personal_bests {
+        println!(
+            "  {:<12} {:>6.1} {:>5.0}%  {}",
+            best.mode.to_string(),
+            best.wpm,
+            best.accuracy,
+            format_date(best.timestamp)
+        );
+    }
+
+    println!();
+    println!("Averages");
+    println!("  {:<12} {:>6} {:>6}", "", "wpm", "acc");
+    for (name, avg) in [
+        ("last 10", &summary.last_10),
+        ("last 100", &summary.last_100),
+    ] {
+        if let Some(avg) = avg {
+            println!("  {:<12} {:>6.1} {:>5.0}%", name, avg.wpm, avg.accuracy);
+        }
+    }
+
+    if let Some(trend) = summary.accuracy_trend {
+        println!();
+        println!("Accuracy trend: {:+.1}% (last 10 vs last 100)", trend);
+    }
+}
+
+/// `1h 02m 03s`
+fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}h {:02}m {:02}s", h, m, s)
+    } else {
+        format!("{}m {:02}s", m, s)
+    }
+}
+
+/// `YYYY-MM-DD` in UTC of a unix timestamp
+pub fn format_date(timestamp: u64) -> String {
+    // Howard Hinnant's days_from_civil, in reverse
+    let z = (timestamp / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(mode: TargetStringType, wpm: f64, accuracy: f64) -> Record {
+        serde_json::from_value(serde_json::json!({
+            "version": history::VERSION,
+            "timestamp": 0,
+            "mode": mode,
+            "wpm": wpm,
+            "accuracy": accuracy,
+            "correct": 10,
+            "duration": 30.0,
+            "wpm_history": [],
+            "accuracy_history": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn personal_bests_leave_out_tests_ended_early() {
+        let mut aborted = record(TargetStringType::Words(50), 200., 100.);
+        aborted.completed = false;
+        let records = [
+            record(TargetStringType::Quote, 50., 90.),
+            record(TargetStringType::Words(50), 60., 90.),
+            aborted,
+            record(TargetStringType::Timed(30), 90., 90.),
+            record(TargetStringType::Timed(15), 80., 90.),
+            record(TargetStringType::Timed(15), 70., 90.),
+        ];
+        let summary = summarise(&records);
+        assert_eq!(summary.tests, 6);
+        assert_eq!(summary.total_time, 180.);
+        let bests: Vec<_> = summary
+            .personal_bests
+            .iter()
+            .map(|b| (b.mode, b.wpm))
+            .collect();
+        assert_eq!(
+            bests,
+            [
+                (TargetStringType::Timed(15), 80.),
+                (TargetStringType::Timed(30), 90.),
+                (TargetStringType::Words(50), 60.),
+                (TargetStringType::Quote, 50.),
+            ]
+        );
+    }
+
+    #[test]
+    fn averages_and_accuracy_trend_of_recent_tests() {
+        let words = TargetStringType::Words(50);
+        let mut records = vec![record(words, 40., 70.); 5];
+        records.extend(vec![record(words, 60., 100.); 10]);
+        let mut aborted = record(words, 5., 0.);
+        aborted.completed = false;
+        records.push(aborted);
+        let summary = summarise(&records);
+        let last_10 = summary.last_10.unwrap();
+        assert_eq!(
+            (last_10.tests, last_10.wpm, last_10.accuracy),
+            (10, 60., 100.)
+        );
+        let last_100 = summary.last_100.unwrap();
+        assert_eq!((last_100.tests, last_100.accuracy), (15, 90.));
+        assert_eq!(summary.accuracy_trend, Some(10.));
+
+        let summary = summarise(&records[5..]);
+        assert_eq!(summary.accuracy_trend, None);
+        let summary = summarise(&[]);
+        assert!(summary.last_10.is_none() && summary.personal_bests.is_empty());
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(json.contains("\"total_time\":0.0"), "{}", json);
+    }
+
+    #[test]
+    fn dates_and_durations() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_709_251_199), "2024-02-29");
+        assert_eq!(format_date(1_735_689_600), "2025-01-01");
+        assert_eq!(format_duration(59.6), "1m 00s");
+        assert_eq!(format_duration(3723.), "1h 02m 03s");
+    }
+}
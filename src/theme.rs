@@ -0,0 +1,227 @@
+use tui::style::Color;
+
+/// Colors of everything drawn
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Behind every box, `Reset` leaves the terminal's own background
+    pub background: Color,
+    pub text: Color,
+    pub border: Color,
+    /// Finished words that were typed correctly
+    pub completed: Color,
+    /// Correctly typed characters of a word with mistakes, or still being typed
+    pub correct: Color,
+    pub wrong: Color,
+    /// Behind wrongly typed characters
+    pub wrong_background: Color,
+    /// Characters not typed yet
+    pub untyped: Color,
+    /// The rest of the word being typed
+    pub current: Color,
+    /// The character the ghost has reached
+    pub ghost: Color,
+    /// Text on a colored background: the status, the menu selection and the heatmap
+    pub highlight_text: Color,
+    /// Status before, during and after a test, and in the menu
+    pub ready: Color,
+    pub running: Color,
+    pub done: Color,
+    pub menu: Color,
+    pub wpm: Color,
+    pub accuracy: Color,
+    pub progress: Color,
+    /// Keys of the heatmap that were not typed, then by error rate: none, under 5%,
+    /// under 15% and more
+    pub heat: [Color; 5],
+}
+
+/// Names of the built in themes, the first is the default
+pub const NAMES: [&str; 5] = ["dark", "light", "solarized", "high-contrast", "monochrome"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            background: Color::Black,
+            text: Color::Reset,
+            border: Color::White,
+            completed: Color::White,
+            correct: Color::Green,
+            wrong: Color::Red,
+            wrong_background: Color::Black,
+            untyped: Color::DarkGray,
+            current: Color::Gray,
+            ghost: Color::Blue,
+            highlight_text: Color::Black,
+            ready: Color::Yellow,
+            running: Color::Green,
+            done: Color::Red,
+            menu: Color::Cyan,
+            wpm: Color::Cyan,
+            accuracy: Color::Magenta,
+            progress: Color::White,
+            heat: [
+                Color::DarkGray,
+                Color::Green,
+                Color::Yellow,
+                Color::LightRed,
+                Color::Red,
+            ],
+        }
+    }
+}
+
+impl Theme {
+    /// One of the themes in `NAMES`
+    pub fn builtin(name: &str) -> Result<Theme, String> {
+        let dark = Theme::default();
+        let theme = match name {
+            "dark" => dark,
+            "light" => Theme {
+                background: Color::Reset,
+                border: Color::DarkGray,
+                completed: Color::Black,
+                wrong_background: Color::Reset,
+                untyped: Color::Gray,
+                current: Color::DarkGray,
+                ghost: Color::LightBlue,
+                done: Color::LightRed,
+                menu: Color::LightCyan,
+                wpm: Color::Blue,
+                progress: Color::DarkGray,
+                heat: [
+                    Color::Gray,
+                    Color::LightGreen,
+                    Color::LightYellow,
+                    Color::LightRed,
+                    Color::Red,
+                ],
+                ..dark
+            },
+            "solarized" => {
+                let base03 = Color::Rgb(0x00, 0x2b, 0x36);
+                let base01 = Color::Rgb(0x58, 0x6e, 0x75);
+                let base0 = Color::Rgb(0x83, 0x94, 0x96);
+                let base1 = Color::Rgb(0x93, 0xa1, 0xa1);
+                let yellow = Color::Rgb(0xb5, 0x89, 0x00);
+                let orange = Color::Rgb(0xcb, 0x4b, 0x16);
+                let red = Color::Rgb(0xdc, 0x32, 0x2f);
+                let magenta = Color::Rgb(0xd3, 0x36, 0x82);
+                let blue = Color::Rgb(0x26, 0x8b, 0xd2);
+                let cyan = Color::Rgb(0x2a, 0xa1, 0x98);
+                let green = Color::Rgb(0x85, 0x99, 0x00);
+                Theme {
+                    background: base03,
+                    text: base0,
+                    border: base01,
+                    completed: base1,
+                    correct: green,
+                    wrong: red,
+                    wrong_background: base03,
+                    untyped: base01,
+                    current: base0,
+                    ghost: blue,
+                    highlight_text: base03,
+                    ready: yellow,
+                    running: green,
+                    done: red,
+                    menu: cyan,
+                    wpm: cyan,
+                    accuracy: magenta,
+                    progress: base1,
+                    heat: [base01, green, yellow, orange, red],
+                }
+            }
+            "high-contrast" => Theme {
+                text: Color::White,
+                completed: Color::White,
+                correct: Color::LightGreen,
+                wrong: Color::White,
+                wrong_background: Color::Red,
+                untyped: Color::Gray,
+                current: Color::White,
+                ghost: Color::LightBlue,
+                wpm: Color::LightCyan,
+                accuracy: Color::LightMagenta,
+                ..dark
+            },
+            "monochrome" => Theme {
+                background: Color::Reset,
+                text: Color::Reset,
+                border: Color::Reset,
+                completed: Color::Reset,
+                correct: Color::Reset,
+                // Shown inverted, since there is no color to tell mistakes apart
+                wrong: Color::Black,
+                wrong_background: Color::White,
+                untyped: Color::DarkGray,
+                current: Color::Reset,
+                ghost: Color::Gray,
+                highlight_text: Color::Black,
+                ready: Color::White,
+                running: Color::White,
+                done: Color::White,
+                menu: Color::White,
+                wpm: Color::White,
+                accuracy: Color::Gray,
+                progress: Color::Reset,
+                // Brighter the more mistakes were made
+                heat: [
+                    Color::DarkGray,
+                    Color::Indexed(246),
+                    Color::Indexed(250),
+                    Color::Indexed(253),
+                    Color::White,
+                ],
+            },
+            _ => {
+                return Err(format!(
+                    "unknown theme '{}', expected one of: {}",
+                    name,
+                    NAMES.join(", ")
+                ))
+            }
+        };
+        Ok(theme)
+    }
+}
+
+/// A color by name, like `dark gray` or `LightRed`, as `#rrggbb`, or as a number from the
+/// 256 color palette. `reset` is the terminal's own color
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let name: String = s
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .collect::<String>()
+        .to_lowercase();
+    let color = match name.as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') && hex.len() == 7 && hex.is_ascii() => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+            match (channel(1), channel(3), channel(5)) {
+                (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                _ => return Err(format!("invalid color '{}'", s)),
+            }
+        }
+        index => match index.parse() {
+            Ok(i) => Color::Indexed(i),
+            Err(_) => return Err(format!("unknown color '{}'", s)),
+        },
+    };
+    Ok(color)
+}